@@ -0,0 +1,286 @@
+//! Host-side helpers for building escrow instructions and reading escrow
+//! accounts, behind the `client` feature.
+//!
+//! The builders derive the escrow PDA, its vault and the canonical bump, and
+//! lay out accounts and instruction data the way the handlers expect them.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use pinocchio::error::ProgramError;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+use crate::instructions::EscrowInstructions;
+use crate::state::{Escrow, EscrowCodec, EscrowV2};
+
+pub use crate::error::EscrowError;
+pub use crate::events::EscrowEvent;
+pub use crate::state::EscrowState;
+
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Stands in for a mint to make that side of the escrow native SOL. Pass
+/// [`SYSTEM_PROGRAM_ID`] as its token program too.
+pub const NATIVE_MINT: Pubkey = SYSTEM_PROGRAM_ID;
+
+pub fn program_id() -> Pubkey {
+    Pubkey::new_from_array(crate::ID.to_bytes())
+}
+
+/// The escrow account layout an instruction runs against, which also picks
+/// its discriminator and Make's data encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Zero-copy [`Escrow`].
+    V1,
+    /// wincode [`EscrowV2`].
+    V2,
+}
+
+impl Layout {
+    fn discriminator(self, v1: EscrowInstructions, v2: EscrowInstructions) -> u8 {
+        match self {
+            Layout::V1 => v1 as u8,
+            Layout::V2 => v2 as u8,
+        }
+    }
+}
+
+/// The escrow PDA for `maker`'s offer `seed`, with its canonical bump.
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow".as_ref(), maker.as_ref(), &seed.to_le_bytes()], &program_id())
+}
+
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Where `wallet` holds `mint`: its associated token account, or the wallet
+/// itself for [`NATIVE_MINT`].
+pub fn token_account(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    if mint == &NATIVE_MINT {
+        return *wallet;
+    }
+    associated_token_address(wallet, mint, token_program)
+}
+
+/// The escrow's vault: its associated token account for mint A, or the
+/// escrow itself when mint A is native SOL.
+pub fn vault_address(escrow: &Pubkey, mint_a: &Pubkey, token_program_a: &Pubkey) -> Pubkey {
+    token_account(escrow, mint_a, token_program_a)
+}
+
+/// Decodes an escrow account's data in the given layout.
+pub fn decode_escrow(layout: Layout, data: &[u8]) -> Result<EscrowState, ProgramError> {
+    match layout {
+        Layout::V1 => Escrow::decode(data),
+        Layout::V2 => EscrowV2::decode(data),
+    }
+}
+
+/// The escrow events in a transaction's log messages, in emission order.
+/// Only `Program data:` lines logged by this program itself are decoded, not
+/// those of the programs it invokes or that invoke it.
+pub fn events_from_logs(logs: &[String]) -> Vec<EscrowEvent> {
+    let program = program_id().to_string();
+    let mut invoked = Vec::new();
+    let mut events = Vec::new();
+    for log in logs {
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if invoked.last() == Some(&program) {
+                events.extend(STANDARD.decode(data).ok().and_then(|bytes| EscrowEvent::decode(&bytes).ok()));
+            }
+        } else if let Some((id, status)) = rest.split_once(' ') {
+            if status.starts_with("invoke [") {
+                invoked.push(id.to_string());
+            } else if status == "success" || status.starts_with("failed") {
+                invoked.pop();
+            }
+        }
+    }
+    events
+}
+
+pub struct Make {
+    pub layout: Layout,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// The token program that owns mint A.
+    pub token_program: Pubkey,
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    /// Unix timestamp after which the escrow can no longer be taken, or 0 for
+    /// no expiry.
+    pub expires_at: i64,
+    /// The only account allowed to take, or `None` for anyone.
+    pub taker: Option<Pubkey>,
+}
+
+impl Make {
+    /// Make's instruction data with `bump`, discriminator included. The
+    /// expiry and taker are only sent when either is set.
+    pub fn data(&self, bump: u8) -> Vec<u8> {
+        let mut data = vec![self.layout.discriminator(EscrowInstructions::Make, EscrowInstructions::MakeV2)];
+        data.extend_from_slice(&self.seed.to_le_bytes());
+        match self.layout {
+            Layout::V1 => {
+                data.push(bump);
+                data.extend_from_slice(&self.amount_to_receive.to_le_bytes());
+                data.extend_from_slice(&self.amount_to_give.to_le_bytes());
+            }
+            Layout::V2 => {
+                data.extend_from_slice(&self.amount_to_receive.to_le_bytes());
+                data.extend_from_slice(&self.amount_to_give.to_le_bytes());
+                data.push(bump);
+            }
+        }
+        if self.expires_at != 0 || self.taker.is_some() {
+            data.extend_from_slice(&self.expires_at.to_le_bytes());
+            data.extend_from_slice(self.taker.unwrap_or_default().as_ref());
+        }
+        data
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let (escrow, bump) = escrow_address(&self.maker, self.seed);
+        Instruction {
+            program_id: program_id(),
+            accounts: vec![
+                AccountMeta::new(self.maker, true),
+                AccountMeta::new_readonly(self.mint_a, false),
+                AccountMeta::new_readonly(self.mint_b, false),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(token_account(&self.maker, &self.mint_a, &self.token_program), false),
+                AccountMeta::new(vault_address(&escrow, &self.mint_a, &self.token_program), false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(self.token_program, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            ],
+            data: self.data(bump),
+        }
+    }
+}
+
+/// Take, or TakePartial when `amount` is set.
+pub struct Take {
+    pub layout: Layout,
+    pub taker: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+    pub seed: u64,
+    /// Mint A to buy, or `None` for everything left.
+    pub amount: Option<u64>,
+}
+
+impl Take {
+    pub fn instruction(&self) -> Instruction {
+        let (escrow, _) = escrow_address(&self.maker, self.seed);
+        let data = match self.amount {
+            None => vec![self.layout.discriminator(EscrowInstructions::Take, EscrowInstructions::TakeV2)],
+            Some(amount) => {
+                let mut data = vec![self
+                    .layout
+                    .discriminator(EscrowInstructions::TakePartial, EscrowInstructions::TakePartialV2)];
+                data.extend_from_slice(&amount.to_le_bytes());
+                data
+            }
+        };
+        Instruction {
+            program_id: program_id(),
+            accounts: vec![
+                AccountMeta::new(self.taker, true),
+                AccountMeta::new(self.maker, false),
+                AccountMeta::new_readonly(self.mint_a, false),
+                AccountMeta::new_readonly(self.mint_b, false),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(vault_address(&escrow, &self.mint_a, &self.token_program_a), false),
+                AccountMeta::new(token_account(&self.taker, &self.mint_a, &self.token_program_a), false),
+                AccountMeta::new(token_account(&self.taker, &self.mint_b, &self.token_program_b), false),
+                AccountMeta::new(token_account(&self.maker, &self.mint_b, &self.token_program_b), false),
+                AccountMeta::new_readonly(self.token_program_a, false),
+                AccountMeta::new_readonly(self.token_program_b, false),
+            ],
+            data,
+        }
+    }
+}
+
+pub struct Cancel {
+    pub layout: Layout,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    /// The token program that owns mint A.
+    pub token_program: Pubkey,
+    pub seed: u64,
+}
+
+impl Cancel {
+    pub fn instruction(&self) -> Instruction {
+        let (escrow, _) = escrow_address(&self.maker, self.seed);
+        Instruction {
+            program_id: program_id(),
+            accounts: vec![
+                AccountMeta::new(self.maker, true),
+                AccountMeta::new_readonly(self.mint_a, false),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(vault_address(&escrow, &self.mint_a, &self.token_program), false),
+                AccountMeta::new(token_account(&self.maker, &self.mint_a, &self.token_program), false),
+                AccountMeta::new_readonly(self.token_program, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            data: vec![self.layout.discriminator(EscrowInstructions::Cancel, EscrowInstructions::CancelV2)],
+        }
+    }
+}
+
+/// Changes an open escrow's price and tops up or withdraws mint A so the
+/// vault holds exactly `amount_to_give`, optionally replacing its expiry.
+pub struct Amend {
+    pub layout: Layout,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    /// The token program that owns mint A.
+    pub token_program: Pubkey,
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    /// New expiry, 0 for none, or `None` to keep the current one.
+    pub expires_at: Option<i64>,
+}
+
+impl Amend {
+    pub fn instruction(&self) -> Instruction {
+        let (escrow, _) = escrow_address(&self.maker, self.seed);
+        let mut data = vec![self.layout.discriminator(EscrowInstructions::Amend, EscrowInstructions::AmendV2)];
+        data.extend_from_slice(&self.amount_to_receive.to_le_bytes());
+        data.extend_from_slice(&self.amount_to_give.to_le_bytes());
+        if let Some(expires_at) = self.expires_at {
+            data.extend_from_slice(&expires_at.to_le_bytes());
+        }
+        Instruction {
+            program_id: program_id(),
+            accounts: vec![
+                AccountMeta::new(self.maker, true),
+                AccountMeta::new_readonly(self.mint_a, false),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(vault_address(&escrow, &self.mint_a, &self.token_program), false),
+                AccountMeta::new(token_account(&self.maker, &self.mint_a, &self.token_program), false),
+                AccountMeta::new_readonly(self.token_program, false),
+            ],
+            data,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use pinocchio::error::ProgramError;
+
+/// Escrow failures, returned as `ProgramError::Custom(code)` with the
+/// variant's discriminant as the code. Malformed instruction data, missing
+/// accounts or signatures and the wrong system or ATA program keep their
+/// standard `ProgramError`s.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscrowError {
+    /// The escrow's `expires_at` has passed, or Make was given one already
+    /// in the past.
+    EscrowExpired = 0,
+    /// The escrow names a designated taker and the signer is someone else.
+    TakerNotAllowed = 1,
+    /// Make or Amend with nothing to give, or a partial take of zero or of
+    /// more than the escrow has left.
+    InvalidAmount = 2,
+    /// Make on an escrow address that is already open.
+    EscrowAlreadyExists = 3,
+    /// The escrow account is not owned by this program.
+    InvalidEscrowAccount = 4,
+    /// The escrow account is not the PDA for its maker, seed and bump, or
+    /// Make was given a bump other than the canonical one.
+    InvalidEscrowAddress = 5,
+    /// The maker account is not the escrow's maker.
+    MakerMismatch = 6,
+    /// A mint account is not the one recorded in the escrow.
+    MintMismatch = 7,
+    /// The token program is not SPL Token or Token-2022, or does not own
+    /// the mint it is passed for.
+    InvalidTokenProgram = 8,
+    /// The mint is not an initialized SPL Token or Token-2022 mint.
+    InvalidMint = 9,
+    /// The mint carries a Token-2022 extension the escrow cannot hold.
+    UnsupportedMintExtension = 10,
+    /// A token account is uninitialized, or not owned by its mint's token
+    /// program.
+    InvalidTokenAccount = 11,
+    /// A token account is held by someone other than expected.
+    TokenAccountOwnerMismatch = 12,
+    /// A token account is for a different mint.
+    TokenAccountMintMismatch = 13,
+    /// Make was asked to swap native SOL for native SOL.
+    BothLegsNative = 14,
+    /// The vault is held by the escrow but is not its associated token
+    /// account for mint A.
+    InvalidVault = 15,
+}
+
+impl From<EscrowError> for ProgramError {
+    fn from(error: EscrowError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
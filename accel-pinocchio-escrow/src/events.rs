@@ -0,0 +1,220 @@
+//! Binary event records, emitted with `sol_log_data` so indexers can follow
+//! escrows from transaction logs instead of diffing account states.
+//!
+//! Each event is a single data slice, shown in the logs as
+//! `Program data: <base64>`. It starts with a one-byte tag followed by the
+//! event's fields in order, addresses as 32 raw bytes and integers
+//! little-endian. `expires_at` is 0 for no expiry, and `taker` in `Created`
+//! is all zeroes when anyone may take:
+//!
+//! | Tag | Event       | Fields                                                                                                     |
+//! |-----|-------------|------------------------------------------------------------------------------------------------------------|
+//! | 0   | `Created`   | `escrow, maker, mint_a, mint_b, seed (8), amount_to_receive (8), amount_to_give (8), expires_at (8), taker` |
+//! | 1   | `Taken`     | `escrow, maker, taker, mint_a, mint_b, amount_a (8), amount_b (8), remaining (8)`                          |
+//! | 2   | `Cancelled` | `escrow, maker, mint_a, refunded (8)`                                                                      |
+//! | 3   | `Amended`   | `escrow, maker, amount_to_receive (8), amount_to_give (8), expires_at (8)`                                 |
+//!
+//! V1 and V2 escrows emit the same records.
+
+use pinocchio::error::ProgramError;
+
+use crate::instructions::data::DataReader;
+
+/// Size of the largest record, `Created`.
+pub const MAX_EVENT_LEN: usize = 1 + 5 * 32 + 4 * 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EscrowEvent {
+    /// Make opened an escrow and deposited `amount_to_give` of mint A, in its
+    /// vault or, for native SOL, in the escrow account itself.
+    Created {
+        escrow: [u8; 32],
+        maker: [u8; 32],
+        mint_a: [u8; 32],
+        mint_b: [u8; 32],
+        seed: u64,
+        amount_to_receive: u64,
+        amount_to_give: u64,
+        expires_at: i64,
+        /// The only account allowed to take, or all zeroes for anyone.
+        taker: [u8; 32],
+    },
+    /// A Take or TakePartial swapped `amount_a` of mint A for `amount_b` of
+    /// mint B. The escrow is closed once `remaining` reaches 0.
+    Taken {
+        escrow: [u8; 32],
+        maker: [u8; 32],
+        taker: [u8; 32],
+        mint_a: [u8; 32],
+        mint_b: [u8; 32],
+        amount_a: u64,
+        amount_b: u64,
+        remaining: u64,
+    },
+    /// Cancel closed the escrow and returned `refunded` of mint A to the
+    /// maker.
+    Cancelled {
+        escrow: [u8; 32],
+        maker: [u8; 32],
+        mint_a: [u8; 32],
+        refunded: u64,
+    },
+    /// Amend repriced the escrow, which now holds `amount_to_give` and
+    /// expires at `expires_at`.
+    Amended {
+        escrow: [u8; 32],
+        maker: [u8; 32],
+        amount_to_receive: u64,
+        amount_to_give: u64,
+        expires_at: i64,
+    },
+}
+
+const CREATED: u8 = 0;
+const TAKEN: u8 = 1;
+const CANCELLED: u8 = 2;
+const AMENDED: u8 = 3;
+
+struct EventWriter {
+    buf: [u8; MAX_EVENT_LEN],
+    len: usize,
+}
+
+impl EventWriter {
+    fn new(tag: u8) -> Self {
+        let mut buf = [0; MAX_EVENT_LEN];
+        buf[0] = tag;
+        Self { buf, len: 1 }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl EscrowEvent {
+    fn tag(&self) -> u8 {
+        match self {
+            Self::Created { .. } => CREATED,
+            Self::Taken { .. } => TAKEN,
+            Self::Cancelled { .. } => CANCELLED,
+            Self::Amended { .. } => AMENDED,
+        }
+    }
+
+    fn encode(&self) -> EventWriter {
+        let mut writer = EventWriter::new(self.tag());
+        match self {
+            Self::Created {
+                escrow,
+                maker,
+                mint_a,
+                mint_b,
+                seed,
+                amount_to_receive,
+                amount_to_give,
+                expires_at,
+                taker,
+            } => {
+                writer.write(escrow);
+                writer.write(maker);
+                writer.write(mint_a);
+                writer.write(mint_b);
+                writer.write(&seed.to_le_bytes());
+                writer.write(&amount_to_receive.to_le_bytes());
+                writer.write(&amount_to_give.to_le_bytes());
+                writer.write(&expires_at.to_le_bytes());
+                writer.write(taker);
+            }
+            Self::Taken { escrow, maker, taker, mint_a, mint_b, amount_a, amount_b, remaining } => {
+                writer.write(escrow);
+                writer.write(maker);
+                writer.write(taker);
+                writer.write(mint_a);
+                writer.write(mint_b);
+                writer.write(&amount_a.to_le_bytes());
+                writer.write(&amount_b.to_le_bytes());
+                writer.write(&remaining.to_le_bytes());
+            }
+            Self::Cancelled { escrow, maker, mint_a, refunded } => {
+                writer.write(escrow);
+                writer.write(maker);
+                writer.write(mint_a);
+                writer.write(&refunded.to_le_bytes());
+            }
+            Self::Amended { escrow, maker, amount_to_receive, amount_to_give, expires_at } => {
+                writer.write(escrow);
+                writer.write(maker);
+                writer.write(&amount_to_receive.to_le_bytes());
+                writer.write(&amount_to_give.to_le_bytes());
+                writer.write(&expires_at.to_le_bytes());
+            }
+        }
+        writer
+    }
+
+    /// Logs the event with `sol_log_data`.
+    pub fn emit(&self) {
+        let writer = self.encode();
+        log_data(&[writer.bytes()]);
+    }
+
+    /// Decodes a record, failing with `InvalidInstructionData` on an unknown
+    /// tag or a length that does not match it.
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut reader = DataReader::new(data);
+        let event = match reader.read_u8()? {
+            CREATED => Self::Created {
+                escrow: reader.read_address()?,
+                maker: reader.read_address()?,
+                mint_a: reader.read_address()?,
+                mint_b: reader.read_address()?,
+                seed: reader.read_u64()?,
+                amount_to_receive: reader.read_u64()?,
+                amount_to_give: reader.read_u64()?,
+                expires_at: reader.read_i64()?,
+                taker: reader.read_address()?,
+            },
+            TAKEN => Self::Taken {
+                escrow: reader.read_address()?,
+                maker: reader.read_address()?,
+                taker: reader.read_address()?,
+                mint_a: reader.read_address()?,
+                mint_b: reader.read_address()?,
+                amount_a: reader.read_u64()?,
+                amount_b: reader.read_u64()?,
+                remaining: reader.read_u64()?,
+            },
+            CANCELLED => Self::Cancelled {
+                escrow: reader.read_address()?,
+                maker: reader.read_address()?,
+                mint_a: reader.read_address()?,
+                refunded: reader.read_u64()?,
+            },
+            AMENDED => Self::Amended {
+                escrow: reader.read_address()?,
+                maker: reader.read_address()?,
+                amount_to_receive: reader.read_u64()?,
+                amount_to_give: reader.read_u64()?,
+                expires_at: reader.read_i64()?,
+            },
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        reader.finish()?;
+        Ok(event)
+    }
+}
+
+fn log_data(data: &[&[u8]]) {
+    #[cfg(target_os = "solana")]
+    unsafe {
+        pinocchio::syscalls::sol_log_data(data.as_ptr() as *const u8, data.len() as u64);
+    }
+    #[cfg(not(target_os = "solana"))]
+    core::hint::black_box(data);
+}
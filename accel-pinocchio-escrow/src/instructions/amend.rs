@@ -0,0 +1,150 @@
+use pinocchio::{
+    AccountView, ProgramResult, cpi::{Seed, Signer}, error::ProgramError,
+};
+use pinocchio_pubkey::derive_address;
+
+use crate::error::EscrowError;
+use crate::events::EscrowEvent;
+use crate::instructions::checks::{check_signer, is_expired};
+use crate::instructions::data::DataReader;
+use crate::instructions::native::{check_leg, move_lamports};
+use crate::instructions::token::{TransferChecked, check_token_account, check_vault};
+use crate::state::EscrowCodec;
+
+/// Instruction data: `amount_to_receive (8) | amount_to_give (8)`,
+/// optionally followed by `expires_at (8)`, little-endian. The escrow now
+/// offers `amount_to_give` of mint A, all of it still in the vault, for
+/// `amount_to_receive` of mint B; the maker tops up or withdraws the
+/// difference from what is left. With `expires_at` the expiry is replaced as
+/// in Make, 0 for never, so an expired offer can be extended instead of
+/// cancelled and made again. The designated taker cannot be changed.
+pub fn process_amend_instruction<C: EscrowCodec>(
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    let [
+        maker,
+        mint_a,
+        escrow_account,
+        escrow_ata,
+        maker_ata,
+        token_program,
+        ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut reader = DataReader::new(data);
+    let amount_to_receive = reader.read_u64()?;
+    let amount_to_give = reader.read_u64()?;
+    let expires_at = if reader.is_empty() { None } else { Some(reader.read_i64()?) };
+    reader.finish()?;
+
+    // Withdrawing everything is a Cancel.
+    if amount_to_give == 0 {
+        return Err(EscrowError::InvalidAmount.into());
+    }
+    if let Some(expires_at) = expires_at {
+        if is_expired(expires_at)? {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+    }
+
+    check_signer(maker)?;
+    let decimals_a = check_leg(mint_a, token_program)?;
+
+    // Verify escrow account ownership
+    unsafe {
+        if escrow_account.owner() != &crate::ID {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+    }
+
+    let mut escrow_state = C::load(escrow_account)?;
+
+    if escrow_state.maker.as_ref() != maker.address().as_ref() {
+        return Err(EscrowError::MakerMismatch.into());
+    }
+
+    if escrow_state.mint_a.as_ref() != mint_a.address().as_ref() {
+        return Err(EscrowError::MintMismatch.into());
+    }
+
+    let bump = escrow_state.bump;
+    let seed_bytes = escrow_state.seed.to_le_bytes();
+    let seed = [b"escrow".as_ref(), maker.address().as_ref(), &seed_bytes, &[bump]];
+    let escrow_pda = derive_address(&seed, None, &crate::ID.to_bytes());
+    if escrow_pda != *escrow_account.address().as_array() {
+        return Err(EscrowError::InvalidEscrowAddress.into());
+    }
+
+    // Verify maker's ATA and the vault
+    if decimals_a.is_some() {
+        check_token_account(maker_ata, maker.address(), mint_a)?;
+        check_vault(escrow_ata, escrow_account, mint_a, token_program)?;
+    }
+
+    let remaining = escrow_state.remaining;
+    if amount_to_give > remaining {
+        // Top up the vault, or the escrow's lamports, from the maker
+        match decimals_a {
+            Some(decimals_a) => TransferChecked {
+                from: maker_ata,
+                mint: mint_a,
+                to: escrow_ata,
+                authority: maker,
+                amount: amount_to_give - remaining,
+                decimals: decimals_a,
+                token_program,
+            }.invoke()?,
+            None => pinocchio_system::instructions::Transfer {
+                from: maker,
+                to: escrow_account,
+                lamports: amount_to_give - remaining,
+            }.invoke()?,
+        }
+    } else if amount_to_give < remaining {
+        // Return the difference to the maker
+        match decimals_a {
+            Some(decimals_a) => {
+                let bump_bytes = [bump];
+                let signer_seeds = [
+                    Seed::from(b"escrow"),
+                    Seed::from(maker.address().as_array()),
+                    Seed::from(&seed_bytes),
+                    Seed::from(&bump_bytes),
+                ];
+                let signer = Signer::from(&signer_seeds);
+
+                TransferChecked {
+                    from: escrow_ata,
+                    mint: mint_a,
+                    to: maker_ata,
+                    authority: escrow_account,
+                    amount: remaining - amount_to_give,
+                    decimals: decimals_a,
+                    token_program,
+                }.invoke_signed(&[signer])?;
+            }
+            None => move_lamports(escrow_account, maker, remaining - amount_to_give)?,
+        }
+    }
+
+    escrow_state.amount_to_receive = amount_to_receive;
+    escrow_state.amount_to_give = amount_to_give;
+    escrow_state.remaining = amount_to_give;
+    if let Some(expires_at) = expires_at {
+        escrow_state.expires_at = expires_at;
+    }
+    C::store(&escrow_state, escrow_account)?;
+
+    EscrowEvent::Amended {
+        escrow: *escrow_account.address().as_array(),
+        maker: escrow_state.maker,
+        amount_to_receive,
+        amount_to_give,
+        expires_at: escrow_state.expires_at,
+    }.emit();
+
+    Ok(())
+}
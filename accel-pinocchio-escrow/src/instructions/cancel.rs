@@ -3,9 +3,14 @@ use pinocchio::{
 };
 use pinocchio_pubkey::derive_address;
 
-use crate::state::Escrow;
+use crate::error::EscrowError;
+use crate::events::EscrowEvent;
+use crate::instructions::checks::{check_signer, is_expired};
+use crate::instructions::native::check_leg;
+use crate::instructions::token::{CloseAccount, TransferChecked, amount, check_token_account, check_vault};
+use crate::state::EscrowCodec;
 
-pub fn process_cancel_instruction(
+pub fn process_cancel_instruction<C: EscrowCodec>(
     accounts: &[AccountView],
     _data: &[u8],
 ) -> ProgramResult {
@@ -21,87 +26,98 @@ pub fn process_cancel_instruction(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // Verify maker is signer
-    if !maker.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    let decimals_a = check_leg(mint_a, token_program)?;
 
     // Verify escrow account ownership
     unsafe {
         if escrow_account.owner() != &crate::ID {
-            return Err(ProgramError::IllegalOwner);
+            return Err(EscrowError::InvalidEscrowAccount.into());
         }
     }
 
-    let bump;
-    {
-        let escrow_state = Escrow::from_account_info(escrow_account)?;
+    let escrow_state = C::load(escrow_account)?;
 
-        if escrow_state.maker().as_ref() != maker.address().as_ref() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    if escrow_state.maker.as_ref() != maker.address().as_ref() {
+        return Err(EscrowError::MakerMismatch.into());
+    }
 
-        if escrow_state.mint_a().as_ref() != mint_a.address().as_ref() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    if escrow_state.mint_a.as_ref() != mint_a.address().as_ref() {
+        return Err(EscrowError::MintMismatch.into());
+    }
 
-        bump = escrow_state.bump;
+    let bump = escrow_state.bump;
+    let seed_bytes = escrow_state.seed.to_le_bytes();
+    let expires_at = escrow_state.expires_at;
+
+    // Only the maker can cancel a live escrow; once expired anyone can,
+    // and the funds still go back to the maker.
+    if !is_expired(expires_at)? {
+        check_signer(maker)?;
     }
     
-    let seed = [b"escrow".as_ref(), maker.address().as_ref(), &[bump]];
+    let seed = [b"escrow".as_ref(), maker.address().as_ref(), &seed_bytes, &[bump]];
     let escrow_pda = derive_address(&seed, None, &crate::ID.to_bytes());
     if escrow_pda != *escrow_account.address().as_array() {
-        return Err(ProgramError::InvalidSeeds);
+        return Err(EscrowError::InvalidEscrowAddress.into());
     }
 
-    // Verify maker's ATA
-    {
-        let maker_ata_state = pinocchio_token::state::TokenAccount::from_account_view(&maker_ata)?;
-        if maker_ata_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-    }
-    
-    let vault_balance;
-    {
-        let escrow_ata_state =
-            pinocchio_token::state::TokenAccount::from_account_view(&escrow_ata)?;
-        vault_balance = escrow_ata_state.amount();
-    }
+    // Native mint A is still in the escrow's lamports and goes back to the
+    // maker when it is closed below.
+    let refunded = match decimals_a {
+        Some(decimals_a) => {
+            // Verify maker's ATA and the vault
+            check_token_account(maker_ata, maker.address(), mint_a)?;
+            check_vault(escrow_ata, escrow_account, mint_a, token_program)?;
 
-    // Create signer seeds for escrow PDA
-    let bump_bytes = [bump];
-    let signer_seeds = [
-        Seed::from(b"escrow"),
-        Seed::from(maker.address().as_array()),
-        Seed::from(&bump_bytes),
-    ];
-    let signer = Signer::from(&signer_seeds);
-
-    // Transfer tokens back from escrow vault to maker
-    if vault_balance > 0 {
-        pinocchio_token::instructions::Transfer {
-            from: escrow_ata,
-            to: maker_ata,
-            authority: escrow_account,
-            amount: vault_balance,
-        }.invoke_signed(&[signer.clone()])?;
-    }
+            let vault_balance = amount(escrow_ata)?;
+
+            // Create signer seeds for escrow PDA
+            let bump_bytes = [bump];
+            let signer_seeds = [
+                Seed::from(b"escrow"),
+                Seed::from(maker.address().as_array()),
+                Seed::from(&seed_bytes),
+                Seed::from(&bump_bytes),
+            ];
+            let signer = Signer::from(&signer_seeds);
+
+            // Transfer tokens back from escrow vault to maker
+            if vault_balance > 0 {
+                TransferChecked {
+                    from: escrow_ata,
+                    mint: mint_a,
+                    to: maker_ata,
+                    authority: escrow_account,
+                    amount: vault_balance,
+                    decimals: decimals_a,
+                    token_program,
+                }.invoke_signed(&[signer.clone()])?;
+            }
 
-    // Close the escrow vault token account
-    pinocchio_token::instructions::CloseAccount {
-        account: escrow_ata,
-        destination: maker,
-        authority: escrow_account,
-    }.invoke_signed(&[signer.clone()])?;
+            // Close the escrow vault token account
+            CloseAccount {
+                account: escrow_ata,
+                destination: maker,
+                authority: escrow_account,
+                token_program,
+            }.invoke_signed(&[signer.clone()])?;
+
+            vault_balance
+        }
+        None => escrow_state.remaining,
+    };
 
     // Close the escrow account and return lamports to maker
     maker.set_lamports(maker.lamports() + escrow_account.lamports());
     escrow_account.set_lamports(0);
     escrow_account.resize(0);
 
+    EscrowEvent::Cancelled {
+        escrow: *escrow_account.address().as_array(),
+        maker: escrow_state.maker,
+        mint_a: escrow_state.mint_a,
+        refunded,
+    }.emit();
+
     Ok(())
 }
\ No newline at end of file
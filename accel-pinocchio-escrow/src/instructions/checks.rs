@@ -0,0 +1,39 @@
+use pinocchio::{
+    AccountView, Address, ProgramResult, error::ProgramError,
+    sysvars::{Sysvar, clock::Clock},
+};
+
+use crate::error::EscrowError;
+
+pub fn check_signer(account: &AccountView) -> ProgramResult {
+    if !account.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Checks that a program account passed in is the one the handler will CPI
+/// into, so a look-alike program cannot be swapped in.
+pub fn check_program(account: &AccountView, id: &Address) -> ProgramResult {
+    if account.address() != id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Whether an escrow with this `expires_at` (0 for never) has expired.
+pub fn is_expired(expires_at: i64) -> Result<bool, ProgramError> {
+    Ok(expires_at != 0 && Clock::get()?.unix_timestamp >= expires_at)
+}
+
+/// Rejects a Take on an expired escrow, or by anyone but the escrow's
+/// designated taker when it has one.
+pub fn check_take_terms(expires_at: i64, designated_taker: &[u8; 32], taker: &AccountView) -> ProgramResult {
+    if is_expired(expires_at)? {
+        return Err(EscrowError::EscrowExpired.into());
+    }
+    if designated_taker != &[0; 32] && designated_taker != taker.address().as_array() {
+        return Err(EscrowError::TakerNotAllowed.into());
+    }
+    Ok(())
+}
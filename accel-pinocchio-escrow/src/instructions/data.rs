@@ -0,0 +1,49 @@
+use pinocchio::error::ProgramError;
+
+/// Reads little-endian fields off the front of instruction data, failing with
+/// `InvalidInstructionData` instead of reading past the end.
+pub struct DataReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DataReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ProgramError> {
+        let (head, rest) = self.data.split_first_chunk::<N>()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        self.data = rest;
+        Ok(*head)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ProgramError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, ProgramError> {
+        Ok(u64::from_le_bytes(self.take::<8>()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, ProgramError> {
+        Ok(i64::from_le_bytes(self.take::<8>()?))
+    }
+
+    pub fn read_address(&mut self) -> Result<[u8; 32], ProgramError> {
+        self.take::<32>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Rejects trailing bytes, so a payload built for a different layout is
+    /// not silently accepted.
+    pub fn finish(self) -> Result<(), ProgramError> {
+        if !self.data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(())
+    }
+}
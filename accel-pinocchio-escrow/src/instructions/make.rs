@@ -1,14 +1,102 @@
 use pinocchio::{
-    AccountView, ProgramResult, cpi::{Seed, Signer}, error::ProgramError, sysvars::{Sysvar, rent::Rent}
+    AccountView, Address, ProgramResult, cpi::{Seed, Signer}, error::ProgramError, sysvars::{Sysvar, rent::Rent}
 };
-use pinocchio_pubkey::derive_address;
 use pinocchio_system::instructions::CreateAccount;
+use wincode::SchemaRead;
 
-use crate::state::Escrow;
+use crate::error::EscrowError;
+use crate::events::EscrowEvent;
+use crate::instructions::checks::{check_program, check_signer, is_expired};
+use crate::instructions::native::{check_leg, is_native};
+use crate::instructions::token::{TransferChecked, check_mint, check_token_account};
+use crate::instructions::data::DataReader;
+use crate::state::{EscrowCodec, EscrowState};
 
-pub fn process_make_instruction(
+/// Make's instruction data:
+/// `seed (8) | bump (1) | amount_to_receive (8) | amount_to_give (8)`,
+/// optionally followed by `expires_at (8) | taker (32)`, integers
+/// little-endian. Without the tail the escrow never expires and anyone can
+/// take it. `bump` must be the escrow PDA's canonical bump.
+pub struct MakeInstructionData {
+    pub seed: u64,
+    pub bump: u8,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    pub expires_at: i64,
+    pub taker: [u8; 32],
+}
+
+impl TryFrom<&[u8]> for MakeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let mut reader = DataReader::new(data);
+        let mut ix_data = Self {
+            seed: reader.read_u64()?,
+            bump: reader.read_u8()?,
+            amount_to_receive: reader.read_u64()?,
+            amount_to_give: reader.read_u64()?,
+            expires_at: 0,
+            taker: [0; 32],
+        };
+        if !reader.is_empty() {
+            ix_data.expires_at = reader.read_i64()?;
+            ix_data.taker = reader.read_address()?;
+        }
+        reader.finish()?;
+        Ok(ix_data)
+    }
+}
+
+#[derive(SchemaRead)]
+pub struct MakeV2InstructionData {
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    pub bump: u8,
+}
+
+impl MakeV2InstructionData {
+    pub const LEN: usize = 8 + 8 + 8 + 1;
+}
+
+/// Optional tail of MakeV2's data. Without it the escrow never expires and
+/// anyone can take it.
+#[derive(SchemaRead, Default)]
+pub struct MakeV2Terms {
+    pub expires_at: i64,
+    pub taker: [u8; 32],
+}
+
+impl MakeInstructionData {
+    /// Decodes MakeV2's wincode instruction data.
+    pub fn from_v2(data: &[u8]) -> Result<Self, ProgramError> {
+        let (data, terms) = data
+            .split_at_checked(MakeV2InstructionData::LEN)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let ix_data = wincode::deserialize::<MakeV2InstructionData>(data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        let terms = match terms {
+            [] => MakeV2Terms::default(),
+            terms if terms.len() == 8 + 32 => wincode::deserialize::<MakeV2Terms>(terms)
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+
+        Ok(Self {
+            seed: ix_data.seed,
+            bump: ix_data.bump,
+            amount_to_receive: ix_data.amount_to_receive,
+            amount_to_give: ix_data.amount_to_give,
+            expires_at: terms.expires_at,
+            taker: terms.taker,
+        })
+    }
+}
+
+pub fn process_make_instruction<C: EscrowCodec>(
     accounts: &[AccountView],
-    data: &[u8],
+    ix_data: MakeInstructionData,
 ) -> ProgramResult {
 
     let [
@@ -20,77 +108,122 @@ pub fn process_make_instruction(
         escrow_ata,
         system_program,
         token_program,
-        _associated_token_program@ ..
+        associated_token_program,
+        ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    {
-        let maker_ata_state = pinocchio_token::state::TokenAccount::from_account_view(&maker_ata)?;
-        if maker_ata_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    let MakeInstructionData { seed, bump, amount_to_receive, amount_to_give, expires_at, taker } = ix_data;
+    // An escrow with nothing to give could never be priced or taken.
+    if amount_to_give == 0 {
+        return Err(EscrowError::InvalidAmount.into());
+    }
+    if is_expired(expires_at)? {
+        return Err(EscrowError::EscrowExpired.into());
     }
 
-    let bump = data[0];
-    let seed = [b"escrow".as_ref(), maker.address().as_ref(), &[bump]];
-    let seeds = &seed[..];
+    check_signer(maker)?;
+    check_program(system_program, &pinocchio_system::ID)?;
+    check_program(associated_token_program, &pinocchio_associated_token_account::ID)?;
+    // `token_program` is whichever token program owns mint A; mint B may
+    // belong to either and is only recorded here. Either side can be SOL, but
+    // not both.
+    let decimals_a = check_leg(mint_a, token_program)?;
+    if !is_native(mint_b) {
+        check_mint(mint_b)?;
+    } else if decimals_a.is_none() {
+        return Err(EscrowError::BothLegsNative.into());
+    }
+    if decimals_a.is_some() {
+        check_token_account(maker_ata, maker.address(), mint_a)?;
+    }
 
-    let escrow_account_pda = derive_address(&seed, None, &crate::ID.to_bytes());
-    assert_eq!(escrow_account_pda, *escrow_account.address().as_array());
+    // Only the canonical bump is accepted, so each maker and seed map to
+    // exactly one escrow.
+    let seed_bytes = seed.to_le_bytes();
+    let pda_seeds = [b"escrow".as_ref(), maker.address().as_ref(), &seed_bytes];
+    let (escrow_account_pda, canonical_bump) = Address::find_program_address(&pda_seeds, &crate::ID);
+    if escrow_account_pda != *escrow_account.address() || bump != canonical_bump {
+        return Err(EscrowError::InvalidEscrowAddress.into());
+    }
 
-    let amount_to_receive = unsafe{ *(data.as_ptr().add(1) as *const u64) };
-    let amount_to_give = unsafe{ *(data.as_ptr().add(9) as *const u64) };
+    let bump_bytes = [bump.to_le()];
+    let signer_seeds = [
+        Seed::from(b"escrow"),
+        Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
+        Seed::from(&bump_bytes),
+    ];
+    let seeds = Signer::from(&signer_seeds);
 
-    let bump = [bump.to_le()];
-    let seed = [Seed::from(b"escrow"), Seed::from(maker.address().as_array()), Seed::from(&bump)];
-    let seeds = Signer::from(&seed);
+    // Native mint A is deposited as lamports on top of the escrow's rent.
+    let mut lamports = Rent::get()?.try_minimum_balance(C::LEN)?;
+    if decimals_a.is_none() {
+        lamports = lamports.checked_add(amount_to_give).ok_or(ProgramError::ArithmeticOverflow)?;
+    }
 
     unsafe {
         if escrow_account.owner() != &crate::ID {
             CreateAccount {
                 from: maker,
                 to: escrow_account,
-                lamports: Rent::get()?.try_minimum_balance(Escrow::LEN)?,
-                space: Escrow::LEN as u64,
+                lamports,
+                space: C::LEN as u64,
                 owner: &crate::ID,
             }.invoke_signed(&[seeds.clone()])?;
 
-
-            {
-                let escrow_state = Escrow::from_account_info(escrow_account)?;
-            
-                escrow_state.set_maker(maker.address());
-                escrow_state.set_mint_a(mint_a.address());
-                escrow_state.set_mint_b(mint_b.address());
-                escrow_state.set_amount_to_receive(amount_to_receive);
-                escrow_state.set_amount_to_give(amount_to_give);  
-                escrow_state.bump = data[0];
-            }
+            let escrow_state = EscrowState {
+                seed,
+                maker: *maker.address().as_array(),
+                mint_a: *mint_a.address().as_array(),
+                mint_b: *mint_b.address().as_array(),
+                amount_to_receive,
+                amount_to_give,
+                remaining: amount_to_give,
+                expires_at,
+                taker,
+                bump,
+            };
+            C::store(&escrow_state, escrow_account)?;
         }
         else {
-            return Err(ProgramError::IllegalOwner);
+            return Err(EscrowError::EscrowAlreadyExists.into());
         }
     }
 
-    pinocchio_associated_token_account::instructions::Create {
-        funding_account: maker,
-        account: escrow_ata,
-        wallet: escrow_account,
-        mint: mint_a,
-        token_program: token_program,
-        system_program: system_program,
-    }.invoke()?;
-
-    pinocchio_token::instructions::Transfer {
-        from: maker_ata,
-        to: escrow_ata,
-        authority: maker,
-        amount: amount_to_give,
-    }.invoke()?;
+    if let Some(decimals_a) = decimals_a {
+        pinocchio_associated_token_account::instructions::Create {
+            funding_account: maker,
+            account: escrow_ata,
+            wallet: escrow_account,
+            mint: mint_a,
+            token_program: token_program,
+            system_program: system_program,
+        }.invoke()?;
+
+        TransferChecked {
+            from: maker_ata,
+            mint: mint_a,
+            to: escrow_ata,
+            authority: maker,
+            amount: amount_to_give,
+            decimals: decimals_a,
+            token_program,
+        }.invoke()?;
+    }
+
+    EscrowEvent::Created {
+        escrow: *escrow_account.address().as_array(),
+        maker: *maker.address().as_array(),
+        mint_a: *mint_a.address().as_array(),
+        mint_b: *mint_b.address().as_array(),
+        seed,
+        amount_to_receive,
+        amount_to_give,
+        expires_at,
+        taker,
+    }.emit();
 
     Ok(())
-}
\ No newline at end of file
+}
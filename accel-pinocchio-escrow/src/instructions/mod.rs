@@ -1,19 +1,22 @@
 pub mod make;
 pub mod take;
 pub mod cancel;
-pub mod make2;
-pub mod take2;
-pub mod cancel2;
+pub mod amend;
+pub mod checks;
+pub mod data;
+pub mod native;
+pub mod token;
 
 pub use make::*;
 pub use take::*;
 pub use cancel::*;
-pub use make2::*;
-pub use take2::*;
-pub use cancel2::*;
+pub use amend::*;
 
 use pinocchio::error::ProgramError;
 
+/// The `V2` variants run the same handlers as their V1 counterparts over the
+/// wincode [`EscrowV2`](crate::state::EscrowV2) layout instead of the
+/// zero-copy [`Escrow`](crate::state::Escrow).
 pub enum EscrowInstructions {
     Make = 0,
     Take = 1,
@@ -21,6 +24,10 @@ pub enum EscrowInstructions {
     MakeV2 = 3,
     TakeV2 = 4,
     CancelV2 = 5,
+    TakePartial = 6,
+    TakePartialV2 = 7,
+    Amend = 8,
+    AmendV2 = 9,
 }
 
 impl TryFrom<&u8> for EscrowInstructions {
@@ -34,6 +41,10 @@ impl TryFrom<&u8> for EscrowInstructions {
             3 => Ok(EscrowInstructions::MakeV2),
             4 => Ok(EscrowInstructions::TakeV2),
             5 => Ok(EscrowInstructions::CancelV2),
+            6 => Ok(EscrowInstructions::TakePartial),
+            7 => Ok(EscrowInstructions::TakePartialV2),
+            8 => Ok(EscrowInstructions::Amend),
+            9 => Ok(EscrowInstructions::AmendV2),
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
@@ -0,0 +1,39 @@
+//! Native SOL legs.
+//!
+//! Either side of an escrow can be SOL instead of an SPL token. A native leg
+//! is marked by passing the system program in place of both its mint and its
+//! token program, and is recorded in the escrow as the system program's
+//! all-zero address. Native mint A is held in the escrow PDA's own lamports,
+//! on top of its rent, so there is no vault; the token account slots of a
+//! native leg are ignored, and the client fills them with the wallets
+//! themselves.
+
+use pinocchio::{AccountView, ProgramResult, error::ProgramError};
+
+use crate::instructions::checks::check_program;
+use crate::instructions::token::check_mint_program;
+
+pub fn is_native(mint: &AccountView) -> bool {
+    mint.address() == &pinocchio_system::ID
+}
+
+/// Checks one side of the swap: a native leg must name the system program as
+/// its token program, anything else must be a mint of `token_program`.
+/// Returns the mint's decimals, or `None` for SOL.
+pub fn check_leg(mint: &AccountView, token_program: &AccountView) -> Result<Option<u8>, ProgramError> {
+    if is_native(mint) {
+        check_program(token_program, &pinocchio_system::ID)?;
+        return Ok(None);
+    }
+    check_mint_program(mint, token_program).map(Some)
+}
+
+/// Moves lamports out of an account this program owns, which the system
+/// program cannot debit for it.
+pub fn move_lamports(from: &AccountView, to: &AccountView, lamports: u64) -> ProgramResult {
+    let from_lamports = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    let to_lamports = to.lamports().checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+    from.set_lamports(from_lamports);
+    to.set_lamports(to_lamports);
+    Ok(())
+}
@@ -3,12 +3,36 @@ use pinocchio::{
 };
 use pinocchio_pubkey::derive_address;
 
-use crate::state::{Escrow, escrow};
+use crate::error::EscrowError;
+use crate::events::EscrowEvent;
+use crate::instructions::checks::{check_signer, check_take_terms};
+use crate::instructions::data::DataReader;
+use crate::instructions::native::{check_leg, move_lamports};
+use crate::instructions::token::{CloseAccount, TransferChecked, check_token_account, check_vault};
+use crate::state::{EscrowCodec, price};
 
-pub fn process_take_instruction(
+pub fn process_take_instruction<C: EscrowCodec>(
     accounts: &[AccountView],
     _data: &[u8],
 ) -> ProgramResult {
+    take::<C>(accounts, None)
+}
+
+/// Instruction data: `amount (8)`, the mint A to buy, little-endian.
+pub fn process_take_partial_instruction<C: EscrowCodec>(
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    let mut reader = DataReader::new(data);
+    let amount = reader.read_u64()?;
+    reader.finish()?;
+
+    take::<C>(accounts, Some(amount))
+}
+
+/// Swaps `amount` of mint A, or everything left when `None`, and closes the
+/// escrow once the vault is empty.
+fn take<C: EscrowCodec>(accounts: &[AccountView], amount: Option<u64>) -> ProgramResult {
     let [
         taker,
         maker,
@@ -19,93 +43,137 @@ pub fn process_take_instruction(
         taker_ata_a,
         taker_ata_b,
         maker_ata_b,
-        token_program,
-        _system_program @ ..
+        token_program_a,
+        token_program_b,
+        ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     // Verify taker is signer
-    if !taker.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    // Load escrow state
-    let escrow_state = Escrow::from_account_info(escrow_account)?;
+    check_signer(taker)?;
+    let decimals_a = check_leg(mint_a, token_program_a)?;
+    let decimals_b = check_leg(mint_b, token_program_b)?;
 
     // Verify escrow account ownership
     unsafe {
         if escrow_account.owner() != &crate::ID {
-            return Err(ProgramError::IllegalOwner);
+            return Err(EscrowError::InvalidEscrowAccount.into());
         }
     }
 
+    // Load escrow state
+    let mut escrow_state = C::load(escrow_account)?;
+
     // Verify maker matches
-    if escrow_state.maker().as_ref() != maker.address().as_ref() {
-        return Err(ProgramError::InvalidAccountData);
+    if escrow_state.maker.as_ref() != maker.address().as_ref() {
+        return Err(EscrowError::MakerMismatch.into());
     }
 
     // Verify mints match
-    if escrow_state.mint_a().as_ref() != mint_a.address().as_ref() {
-        return Err(ProgramError::InvalidAccountData);
+    if escrow_state.mint_a.as_ref() != mint_a.address().as_ref() {
+        return Err(EscrowError::MintMismatch.into());
     }
-    if escrow_state.mint_b().as_ref() != mint_b.address().as_ref() {
-        return Err(ProgramError::InvalidAccountData);
+    if escrow_state.mint_b.as_ref() != mint_b.address().as_ref() {
+        return Err(EscrowError::MintMismatch.into());
     }
 
+    check_take_terms(escrow_state.expires_at, &escrow_state.taker, taker)?;
+
     // Verify PDA
     let bump = escrow_state.bump;
-    let seed = [b"escrow".as_ref(), maker.address().as_ref(), &[bump]];
+    let seed_bytes = escrow_state.seed.to_le_bytes();
+    let seed = [b"escrow".as_ref(), maker.address().as_ref(), &seed_bytes, &[bump]];
     let escrow_pda = derive_address(&seed, None, &crate::ID.to_bytes());
     if escrow_pda != *escrow_account.address().as_array() {
-        return Err(ProgramError::InvalidSeeds);
+        return Err(EscrowError::InvalidEscrowAddress.into());
     }
 
-    let amount_to_receive = escrow_state.amount_to_receive();
-    let amount_to_give = escrow_state.amount_to_give();
-    
-    {
-        // Verify taker's token account for mint_b
-        let taker_ata_b_state = pinocchio_token::state::TokenAccount::from_account_view(&taker_ata_b)?;
-        if taker_ata_b_state.owner() != taker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if taker_ata_b_state.mint() != mint_b.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    let remaining = escrow_state.remaining;
+    let amount = amount.unwrap_or(remaining);
+    if amount == 0 || amount > remaining {
+        return Err(EscrowError::InvalidAmount.into());
+    }
+    let payment = price(amount, escrow_state.amount_to_receive, escrow_state.amount_to_give)?;
+
+    // Verify every token account the swap touches
+    if decimals_a.is_some() {
+        check_vault(escrow_ata, escrow_account, mint_a, token_program_a)?;
+        check_token_account(taker_ata_a, taker.address(), mint_a)?;
+    }
+    if decimals_b.is_some() {
+        check_token_account(taker_ata_b, taker.address(), mint_b)?;
+        check_token_account(maker_ata_b, maker.address(), mint_b)?;
     }
 
-    // Transfer mint_b tokens from taker to maker (amount_to_receive)
-    pinocchio_token::instructions::Transfer {
-        from: taker_ata_b,
-        to: maker_ata_b,
-        authority: taker,
-        amount: amount_to_receive,
-    }.invoke()?;
+    // Pay the maker in mint B at the escrow's ratio
+    match decimals_b {
+        Some(decimals_b) => TransferChecked {
+            from: taker_ata_b,
+            mint: mint_b,
+            to: maker_ata_b,
+            authority: taker,
+            amount: payment,
+            decimals: decimals_b,
+            token_program: token_program_b,
+        }.invoke()?,
+        None => pinocchio_system::instructions::Transfer {
+            from: taker,
+            to: maker,
+            lamports: payment,
+        }.invoke()?,
+    }
 
     // Create signer seeds for escrow PDA
     let bump_bytes = [bump];
     let signer_seeds = [
         Seed::from(b"escrow"),
         Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
         Seed::from(&bump_bytes),
     ];
     let signer = Signer::from(&signer_seeds);
 
-    // Transfer mint_a tokens from escrow vault to taker (amount_to_give)
-    pinocchio_token::instructions::Transfer {
-        from: escrow_ata,
-        to: taker_ata_a,
-        authority: escrow_account,
-        amount: amount_to_give,
-    }.invoke_signed(&[signer.clone()])?;
-
-    // Close the escrow vault token account
-    pinocchio_token::instructions::CloseAccount {
-        account: escrow_ata,
-        destination: maker,
-        authority: escrow_account,
-    }.invoke_signed(&[signer.clone()])?;
+    // Transfer mint A from the escrow vault, or its own lamports, to taker
+    match decimals_a {
+        Some(decimals_a) => TransferChecked {
+            from: escrow_ata,
+            mint: mint_a,
+            to: taker_ata_a,
+            authority: escrow_account,
+            amount,
+            decimals: decimals_a,
+            token_program: token_program_a,
+        }.invoke_signed(&[signer.clone()])?,
+        None => move_lamports(escrow_account, taker, amount)?,
+    }
+
+    EscrowEvent::Taken {
+        escrow: *escrow_account.address().as_array(),
+        maker: escrow_state.maker,
+        taker: *taker.address().as_array(),
+        mint_a: escrow_state.mint_a,
+        mint_b: escrow_state.mint_b,
+        amount_a: amount,
+        amount_b: payment,
+        remaining: remaining - amount,
+    }.emit();
+
+    if amount < remaining {
+        escrow_state.remaining = remaining - amount;
+        C::store(&escrow_state, escrow_account)?;
+        return Ok(());
+    }
+
+    // Fully filled: close the escrow vault token account
+    if decimals_a.is_some() {
+        CloseAccount {
+            account: escrow_ata,
+            destination: maker,
+            authority: escrow_account,
+            token_program: token_program_a,
+        }.invoke_signed(&[signer.clone()])?;
+    }
 
     // Close the escrow account and return lamports to maker
     maker.set_lamports(maker.lamports() + escrow_account.lamports());
@@ -0,0 +1,206 @@
+//! Token program support that works for both SPL Token and Token-2022.
+//!
+//! `pinocchio_token` hard-codes the legacy program id, so the escrow reads
+//! token accounts and mints from their shared base layout and builds the
+//! `TransferChecked` and `CloseAccount` CPIs itself against whichever program
+//! owns the mint.
+
+use pinocchio::{
+    AccountView, Address, ProgramResult,
+    cpi::{Signer, invoke_signed},
+    error::ProgramError,
+    instruction::{InstructionAccount, InstructionView},
+};
+
+use crate::error::EscrowError;
+
+/// `TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb`
+pub const TOKEN_2022_PROGRAM_ID: Address = Address::new_from_array([
+    6, 221, 246, 225, 238, 117, 143, 222, 24, 66, 93, 188, 228, 108, 205, 218,
+    182, 26, 252, 77, 131, 185, 13, 39, 254, 189, 249, 40, 216, 161, 139, 252,
+]);
+
+const MINT_LEN: usize = 82;
+const ACCOUNT_LEN: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+
+/// Token-2022 mint extensions the escrow refuses: fees would leave the vault
+/// short of `amount_to_give`, a permanent delegate or pause could drain or
+/// freeze it, hooks need accounts the escrow does not pass, and
+/// non-transferable tokens cannot be escrowed at all.
+const REJECTED_EXTENSIONS: [u16; 5] = [
+    1,  // TransferFeeConfig
+    9,  // NonTransferable
+    12, // PermanentDelegate
+    14, // TransferHook
+    26, // Pausable
+];
+
+fn is_token_program(id: &Address) -> bool {
+    id == &pinocchio_token::ID || id == &TOKEN_2022_PROGRAM_ID
+}
+
+pub fn check_token_program(account: &AccountView) -> ProgramResult {
+    if !is_token_program(account.address()) {
+        return Err(EscrowError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+/// The token program that owns `account`, if either does.
+fn token_program_of(account: &AccountView) -> Option<&Address> {
+    let owner = unsafe { account.owner() };
+    is_token_program(owner).then_some(owner)
+}
+
+/// Checks that `mint` is an initialized mint of either token program with no
+/// rejected extensions, and returns its decimals.
+pub fn check_mint(mint: &AccountView) -> Result<u8, ProgramError> {
+    let is_2022 = token_program_of(mint).ok_or(EscrowError::InvalidMint)? == &TOKEN_2022_PROGRAM_ID;
+
+    let data = mint.try_borrow()?;
+    if data.len() < MINT_LEN || data[45] != 1 {
+        return Err(EscrowError::InvalidMint.into());
+    }
+    let decimals = data[44];
+
+    if is_2022 && data.len() > ACCOUNT_LEN {
+        if data[ACCOUNT_LEN] != ACCOUNT_TYPE_MINT {
+            return Err(EscrowError::InvalidMint.into());
+        }
+
+        // TLV entries: `type (u16) | length (u16) | value`
+        let mut extensions = &data[ACCOUNT_LEN + 1..];
+        while let [t0, t1, l0, l1, rest @ ..] = extensions {
+            let extension = u16::from_le_bytes([*t0, *t1]);
+            if REJECTED_EXTENSIONS.contains(&extension) {
+                return Err(EscrowError::UnsupportedMintExtension.into());
+            }
+            let len = u16::from_le_bytes([*l0, *l1]) as usize;
+            extensions = rest.get(len..).ok_or(EscrowError::InvalidMint)?;
+        }
+    }
+
+    Ok(decimals)
+}
+
+/// Checks that `mint` belongs to `token_program`, the program the handler
+/// will CPI into for it, and returns its decimals.
+pub fn check_mint_program(mint: &AccountView, token_program: &AccountView) -> Result<u8, ProgramError> {
+    check_token_program(token_program)?;
+    let decimals = check_mint(mint)?;
+    if token_program_of(mint) != Some(token_program.address()) {
+        return Err(EscrowError::InvalidTokenProgram.into());
+    }
+    Ok(decimals)
+}
+
+/// Checks that `account` is a token account for `mint` held by `owner`, owned
+/// by the same token program as the mint.
+pub fn check_token_account(account: &AccountView, owner: &Address, mint: &AccountView) -> ProgramResult {
+    let token_program = token_program_of(account).ok_or(EscrowError::InvalidTokenAccount)?;
+    if Some(token_program) != token_program_of(mint) {
+        return Err(EscrowError::InvalidTokenAccount.into());
+    }
+
+    let data = account.try_borrow()?;
+    if data.len() < ACCOUNT_LEN || data[108] == 0 {
+        return Err(EscrowError::InvalidTokenAccount.into());
+    }
+    if &data[32..64] != owner.as_ref() {
+        return Err(EscrowError::TokenAccountOwnerMismatch.into());
+    }
+    if &data[0..32] != mint.address().as_ref() {
+        return Err(EscrowError::TokenAccountMintMismatch.into());
+    }
+    Ok(())
+}
+
+/// Checks that `vault` is the escrow's vault: a token account for `mint` held
+/// by `escrow`, at the associated token address Make created it at. Any other
+/// account the escrow happens to hold would leave the real vault stranded.
+pub fn check_vault(
+    vault: &AccountView,
+    escrow: &AccountView,
+    mint: &AccountView,
+    token_program: &AccountView,
+) -> ProgramResult {
+    check_token_account(vault, escrow.address(), mint)?;
+
+    let seeds = [escrow.address().as_ref(), token_program.address().as_ref(), mint.address().as_ref()];
+    let (address, _) = Address::find_program_address(&seeds, &pinocchio_associated_token_account::ID);
+    if vault.address() != &address {
+        return Err(EscrowError::InvalidVault.into());
+    }
+    Ok(())
+}
+
+/// Balance of a token account already checked with [`check_token_account`].
+pub fn amount(account: &AccountView) -> Result<u64, ProgramError> {
+    let data = account.try_borrow()?;
+    let amount = data.get(64..72).ok_or(EscrowError::InvalidTokenAccount)?;
+    Ok(u64::from_le_bytes(amount.try_into().unwrap()))
+}
+
+pub struct TransferChecked<'a> {
+    pub from: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub to: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub amount: u64,
+    pub decimals: u8,
+    pub token_program: &'a AccountView,
+}
+
+impl TransferChecked<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.from.address()),
+            InstructionAccount::readonly(self.mint.address()),
+            InstructionAccount::writable(self.to.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ];
+
+        let mut data = [0u8; 10];
+        data[0] = 12;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        data[9] = self.decimals;
+
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data,
+        };
+
+        invoke_signed(&instruction, &[self.from, self.mint, self.to, self.authority], signers)
+    }
+}
+
+pub struct CloseAccount<'a> {
+    pub account: &'a AccountView,
+    pub destination: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub token_program: &'a AccountView,
+}
+
+impl CloseAccount<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::writable(self.destination.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ];
+
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &[9],
+        };
+
+        invoke_signed(&instruction, &[self.account, self.destination, self.authority], signers)
+    }
+}
@@ -1,11 +1,16 @@
 #![allow(unexpected_cfgs)]
 use pinocchio::{AccountView, entrypoint, Address, ProgramResult, address::declare_id, error::ProgramError};
 
-use crate::instructions::EscrowInstructions;
+use crate::instructions::{EscrowInstructions, MakeInstructionData};
+use crate::state::{Escrow, EscrowV2};
 
 mod tests;
 mod state;
 mod instructions;
+pub mod error;
+pub mod events;
+#[cfg(all(any(test, feature = "client"), not(target_os = "solana")))]
+pub mod client;
 
 entrypoint!(process_instruction);
 
@@ -16,18 +21,24 @@ pub fn process_instruction(
     accounts: &[AccountView],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    assert_eq!(program_id, &ID);
+    if program_id != &ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
 
     let (discriminator, data) = instruction_data.split_first()
         .ok_or(ProgramError::InvalidInstructionData)?;
 
     match EscrowInstructions::try_from(discriminator)? {
-        EscrowInstructions::Make => instructions::process_make_instruction(accounts, data)?,
-        EscrowInstructions::Take => instructions::process_take_instruction(accounts, data)?,
-        EscrowInstructions::Cancel => instructions::process_cancel_instruction(accounts, data)?,
-        EscrowInstructions::MakeV2 => instructions::process_make_instruction_v2(accounts, data)?,
-        EscrowInstructions::TakeV2 => instructions::process_take_instruction_v2(accounts, data)?,
-        EscrowInstructions::CancelV2 => instructions::process_cancel_instruction_v2(accounts, data)?,
+        EscrowInstructions::Make => instructions::process_make_instruction::<Escrow>(accounts, MakeInstructionData::try_from(data)?)?,
+        EscrowInstructions::Take => instructions::process_take_instruction::<Escrow>(accounts, data)?,
+        EscrowInstructions::Cancel => instructions::process_cancel_instruction::<Escrow>(accounts, data)?,
+        EscrowInstructions::MakeV2 => instructions::process_make_instruction::<EscrowV2>(accounts, MakeInstructionData::from_v2(data)?)?,
+        EscrowInstructions::TakeV2 => instructions::process_take_instruction::<EscrowV2>(accounts, data)?,
+        EscrowInstructions::CancelV2 => instructions::process_cancel_instruction::<EscrowV2>(accounts, data)?,
+        EscrowInstructions::TakePartial => instructions::process_take_partial_instruction::<Escrow>(accounts, data)?,
+        EscrowInstructions::TakePartialV2 => instructions::process_take_partial_instruction::<EscrowV2>(accounts, data)?,
+        EscrowInstructions::Amend => instructions::process_amend_instruction::<Escrow>(accounts, data)?,
+        EscrowInstructions::AmendV2 => instructions::process_amend_instruction::<EscrowV2>(accounts, data)?,
     }
     Ok(())
 }
\ No newline at end of file
@@ -1,18 +1,24 @@
-use pinocchio::{AccountView, error::ProgramError};
+use pinocchio::{AccountView, ProgramResult, error::ProgramError};
+
+use super::{EscrowCodec, EscrowState};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Escrow {
+    seed: [u8; 8],
     maker: [u8; 32],
     mint_a: [u8; 32],
     mint_b: [u8; 32],
     amount_to_receive: [u8; 8],
     amount_to_give: [u8; 8],
+    remaining: [u8; 8],
+    expires_at: [u8; 8],
+    taker: [u8; 32],
     pub bump: u8,
 }
 
 impl Escrow {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 32 + 1;
 
     pub fn from_account_info(account_info: &AccountView) -> Result<&mut Self, ProgramError> {
         let mut data = account_info.try_borrow_mut()?;
@@ -27,6 +33,26 @@ impl Escrow {
         Ok(unsafe { &mut *(data.as_mut_ptr() as *mut Self) })
     }
 
+    pub fn from_bytes(data: &[u8]) -> Result<&Self, ProgramError> {
+        if data.len() != Escrow::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if (data.as_ptr() as usize) % core::mem::align_of::<Self>() != 0 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(unsafe { &*(data.as_ptr() as *const Self) })
+    }
+
+    pub fn seed(&self) -> u64 {
+        u64::from_le_bytes(self.seed)
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed.to_le_bytes();
+    }
+
     pub fn maker(&self) -> pinocchio::Address {
         pinocchio::Address::from(self.maker)
     }
@@ -66,4 +92,67 @@ impl Escrow {
     pub fn set_amount_to_give(&mut self, amount: u64) {
         self.amount_to_give = amount.to_le_bytes();
     }
-}
\ No newline at end of file
+
+    /// Mint A still in the vault, waiting to be taken.
+    pub fn remaining(&self) -> u64 {
+        u64::from_le_bytes(self.remaining)
+    }
+
+    pub fn set_remaining(&mut self, amount: u64) {
+        self.remaining = amount.to_le_bytes();
+    }
+
+    /// Unix timestamp after which the escrow can no longer be taken, or 0 for
+    /// no expiry.
+    pub fn expires_at(&self) -> i64 {
+        i64::from_le_bytes(self.expires_at)
+    }
+
+    pub fn set_expires_at(&mut self, expires_at: i64) {
+        self.expires_at = expires_at.to_le_bytes();
+    }
+
+    /// The only account allowed to take, or all zeroes for anyone.
+    pub fn taker(&self) -> [u8; 32] {
+        self.taker
+    }
+
+    pub fn set_taker(&mut self, taker: [u8; 32]) {
+        self.taker = taker;
+    }
+}
+
+impl EscrowCodec for Escrow {
+    const LEN: usize = Escrow::LEN;
+
+    fn decode(data: &[u8]) -> Result<EscrowState, ProgramError> {
+        let escrow = Escrow::from_bytes(data)?;
+        Ok(EscrowState {
+            seed: escrow.seed(),
+            maker: *escrow.maker().as_array(),
+            mint_a: *escrow.mint_a().as_array(),
+            mint_b: *escrow.mint_b().as_array(),
+            amount_to_receive: escrow.amount_to_receive(),
+            amount_to_give: escrow.amount_to_give(),
+            remaining: escrow.remaining(),
+            expires_at: escrow.expires_at(),
+            taker: escrow.taker(),
+            bump: escrow.bump,
+        })
+    }
+
+    fn store(state: &EscrowState, account: &AccountView) -> ProgramResult {
+        let escrow = Escrow::from_account_info(account)?;
+        escrow.set_seed(state.seed);
+        escrow.set_maker(&pinocchio::Address::from(state.maker));
+        escrow.set_mint_a(&pinocchio::Address::from(state.mint_a));
+        escrow.set_mint_b(&pinocchio::Address::from(state.mint_b));
+        escrow.set_amount_to_receive(state.amount_to_receive);
+        escrow.set_amount_to_give(state.amount_to_give);
+        escrow.set_remaining(state.remaining);
+        escrow.set_expires_at(state.expires_at);
+        escrow.set_taker(state.taker);
+        escrow.bump = state.bump;
+        Ok(())
+    }
+}
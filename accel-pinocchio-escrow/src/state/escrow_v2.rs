@@ -1,25 +1,34 @@
-use pinocchio::{AccountView, error::ProgramError};
+use pinocchio::{AccountView, ProgramResult, error::ProgramError};
 use wincode::{SchemaRead, SchemaWrite};
 
+use super::{EscrowCodec, EscrowState};
+
 #[derive(SchemaRead, SchemaWrite, Clone, Debug, Default)]
 pub struct EscrowV2 {
+    pub seed: u64,
     pub maker: [u8; 32],
     pub mint_a: [u8; 32],
     pub mint_b: [u8; 32],
     pub amount_to_receive: u64,
     pub amount_to_give: u64,
+    /// Mint A still in the vault, waiting to be taken.
+    pub remaining: u64,
+    /// Unix timestamp after which the escrow can no longer be taken, or 0 for
+    /// no expiry.
+    pub expires_at: i64,
+    /// The only account allowed to take, or all zeroes for anyone.
+    pub taker: [u8; 32],
     pub bump: u8,
 }
 
 impl EscrowV2 {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 32 + 1;
 
-    pub fn read_from(account_info: &AccountView) -> Result<Self, ProgramError> {
-        let data = account_info.try_borrow()?;
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
         if data.len() < Self::LEN {
             return Err(ProgramError::InvalidAccountData);
         }
-        wincode::deserialize::<Self>(&data)
+        wincode::deserialize::<Self>(data)
             .map_err(|_| ProgramError::InvalidAccountData)
     }
 
@@ -33,4 +42,51 @@ impl EscrowV2 {
         data[..serialized.len()].copy_from_slice(&serialized);
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl EscrowCodec for EscrowV2 {
+    const LEN: usize = EscrowV2::LEN;
+
+    fn decode(data: &[u8]) -> Result<EscrowState, ProgramError> {
+        let EscrowV2 {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            amount_to_receive,
+            amount_to_give,
+            remaining,
+            expires_at,
+            taker,
+            bump,
+        } = EscrowV2::from_bytes(data)?;
+        Ok(EscrowState {
+            seed,
+            maker,
+            mint_a,
+            mint_b,
+            amount_to_receive,
+            amount_to_give,
+            remaining,
+            expires_at,
+            taker,
+            bump,
+        })
+    }
+
+    fn store(state: &EscrowState, account: &AccountView) -> ProgramResult {
+        EscrowV2 {
+            seed: state.seed,
+            maker: state.maker,
+            mint_a: state.mint_a,
+            mint_b: state.mint_b,
+            amount_to_receive: state.amount_to_receive,
+            amount_to_give: state.amount_to_give,
+            remaining: state.remaining,
+            expires_at: state.expires_at,
+            taker: state.taker,
+            bump: state.bump,
+        }
+        .write_to(account)
+    }
+}
@@ -2,4 +2,56 @@ pub mod escrow;
 pub mod escrow_v2;
 
 pub use escrow::*;
-pub use escrow_v2::*;
\ No newline at end of file
+pub use escrow_v2::*;
+
+use pinocchio::{AccountView, ProgramResult, error::ProgramError};
+
+use crate::error::EscrowError;
+
+/// An escrow's fields, independent of how a layout stores them on chain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EscrowState {
+    pub seed: u64,
+    pub maker: [u8; 32],
+    /// All zeroes, the system program's address, for a native SOL leg.
+    pub mint_a: [u8; 32],
+    /// All zeroes, the system program's address, for a native SOL leg.
+    pub mint_b: [u8; 32],
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    /// Mint A still in the vault, waiting to be taken.
+    pub remaining: u64,
+    /// Unix timestamp after which the escrow can no longer be taken, or 0 for
+    /// no expiry.
+    pub expires_at: i64,
+    /// The only account allowed to take, or all zeroes for anyone.
+    pub taker: [u8; 32],
+    pub bump: u8,
+}
+
+/// An on-chain encoding of [`EscrowState`]. The handlers are generic over
+/// it, and the instruction discriminator picks the layout. Every layout,
+/// zero-copy V1 included, is copied into an owned [`EscrowState`] on load.
+pub trait EscrowCodec {
+    /// Size of the escrow account.
+    const LEN: usize;
+
+    fn decode(data: &[u8]) -> Result<EscrowState, ProgramError>;
+
+    fn load(account: &AccountView) -> Result<EscrowState, ProgramError> {
+        Self::decode(&account.try_borrow()?)
+    }
+
+    fn store(state: &EscrowState, account: &AccountView) -> ProgramResult;
+}
+
+/// Mint B owed for `amount` of mint A at the escrow's fixed ratio of
+/// `amount_to_receive` per `amount_to_give`, rounded up so the maker is never
+/// short-changed by a partial fill.
+pub fn price(amount: u64, amount_to_receive: u64, amount_to_give: u64) -> Result<u64, ProgramError> {
+    if amount_to_give == 0 {
+        return Err(EscrowError::InvalidAmount.into());
+    }
+    let owed = (amount as u128 * amount_to_receive as u128).div_ceil(amount_to_give as u128);
+    u64::try_from(owed).map_err(|_| ProgramError::ArithmeticOverflow)
+}
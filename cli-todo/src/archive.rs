@@ -0,0 +1,130 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::Todo;
+use crate::dates::date_from_epoch;
+use crate::output::{relative_age, span};
+
+const DAY: u64 = 86_400;
+
+/// A finished task, kept for `history` and `stats`.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct Completed {
+    #[serde(flatten)]
+    pub todo: Todo,
+    pub list: String,
+    pub completed_at: u64,
+}
+
+impl Completed {
+    /// Seconds between adding the task and completing it.
+    pub fn lead_time(&self) -> u64 {
+        self.completed_at.saturating_sub(self.todo.created_at)
+    }
+}
+
+pub fn render_history(entries: &[&Completed], now: u64) -> String {
+    if entries.is_empty() {
+        return "No completed tasks yet\n".to_string();
+    }
+
+    let rows: Vec<[String; 5]> = entries
+        .iter()
+        .map(|c| {
+            [
+                c.todo.id.to_string(),
+                c.todo.title.clone(),
+                c.list.clone(),
+                relative_age(now, c.completed_at),
+                span(c.lead_time()),
+            ]
+        })
+        .collect();
+
+    let header = ["ID", "TITLE", "LIST", "DONE", "TOOK"];
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(header.map(String::from)).chain(rows) {
+        let mut line = format!("{:>width$}", row[0], width = widths[0]);
+        for (cell, width) in row.iter().zip(widths).skip(1) {
+            line.push_str(&format!("  {:<width$}", cell, width = width));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Completion counts for each of the last `days` days (oldest first, today
+/// last) and the average time from creation to completion over all entries.
+pub fn render_stats(entries: &[&Completed], now: u64, days: u64) -> String {
+    let today = now / DAY;
+    let first = today.saturating_sub(days.saturating_sub(1));
+
+    let mut per_day = vec![0usize; (today - first + 1) as usize];
+    for c in entries {
+        let day = c.completed_at / DAY;
+        if (first..=today).contains(&day) {
+            per_day[(day - first) as usize] += 1;
+        }
+    }
+
+    let mut out = format!("Completed: {}\n", entries.len());
+    if !entries.is_empty() {
+        let total: u64 = entries.iter().map(|c| c.lead_time()).sum();
+        let average = total / entries.len() as u64;
+        out.push_str(&format!("Average time to complete: {}\n", span(average)));
+    }
+
+    out.push('\n');
+    for (offset, count) in per_day.iter().enumerate() {
+        let date = date_from_epoch((first + offset as u64) * DAY);
+        let bar = "#".repeat(*count);
+        out.push_str(format!("{}  {:>3}  {}", date, count, bar).trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completed(id: u64, created_at: u64, completed_at: u64) -> Completed {
+        Completed {
+            todo: Todo { id, title: format!("task {}", id), created_at, ..Default::default() },
+            list: "default".to_string(),
+            completed_at,
+        }
+    }
+
+    #[test]
+    fn stats_counts_per_day_and_averages() {
+        // 2000-02-29T00:00:00Z
+        let day0 = 951_782_400;
+        let a = completed(1, day0 - 2 * 3_600, day0 + 60);
+        let b = completed(2, day0, day0 + DAY + 4 * 3_600);
+        let c = completed(3, day0, day0 + DAY + 5 * 3_600);
+        let old = completed(4, 0, 60);
+
+        let out = render_stats(&[&a, &b, &c, &old], day0 + DAY + 6 * 3_600, 3);
+        assert_eq!(
+            out,
+            "Completed: 4\nAverage time to complete: 14h\n\n\
+             2000-02-28    0\n2000-02-29    1  #\n2000-03-01    2  ##\n"
+        );
+    }
+
+    #[test]
+    fn history_lists_list_and_lead_time() {
+        let a = completed(7, 0, 3 * 3_600);
+        let out = render_history(&[&a], 4 * 3_600);
+        assert_eq!(out, "ID  TITLE   LIST     DONE    TOOK\n 7  task 7  default  1h ago  3h\n");
+    }
+}
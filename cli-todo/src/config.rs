@@ -0,0 +1,118 @@
+use cli_todo::TodoError;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::Backend;
+use crate::output::Format;
+
+/// Defaults read from `config.toml`. Every field is optional and command-line
+/// flags always win over it.
+///
+/// ```toml
+/// file = "~/notes/todo.json"
+/// backend = "json"
+/// list = "work"
+/// format = "table"
+/// notify = 'notify-send "Todo due" "$1"'
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Data file, used instead of the backend's default file name.
+    pub file: Option<PathBuf>,
+    /// Storage backend for the data file.
+    pub backend: Option<Backend>,
+    /// List to use when `--list` is not given.
+    pub list: Option<String>,
+    /// Output format for `list` when `--format` is not given.
+    pub format: Option<Format>,
+    /// Command `watch` runs for each due task when `--exec` is not given.
+    pub notify: Option<String>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/cli-todo/config.toml`, falling back to
+    /// `~/.config/cli-todo/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(".config")))?;
+        Some(base.join("cli-todo").join("config.toml"))
+    }
+
+    /// Reads the config at `path`. A missing file means "no overrides"; a file
+    /// that exists but does not parse is an error rather than silently ignored.
+    pub fn load(path: &Path) -> Result<Self, TodoError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(TodoError::Io { path: path.display().to_string(), source: e }),
+        };
+
+        Self::parse(&text).map_err(|message| TodoError::Config {
+            path: path.display().to_string(),
+            message,
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut config: Config = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        config.file = config.file.map(|file| expand_home(&file));
+        Ok(config)
+    }
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+}
+
+/// Expands a leading `~/` so paths in the config can point into the home
+/// directory the way they would in a shell.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_field() {
+        let config = Config::parse(
+            "file = \"/tmp/todo.json\"\nbackend = \"json\"\nlist = \"work\"\nformat = \"csv\"\n\
+             notify = \"notify-send\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                file: Some(PathBuf::from("/tmp/todo.json")),
+                backend: Some(Backend::Json),
+                list: Some("work".to_string()),
+                format: Some(Format::Csv),
+                notify: Some("notify-send".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_values() {
+        assert!(Config::parse("colour = \"blue\"\n").unwrap_err().contains("unknown field"));
+        assert!(Config::parse("backend = \"floppy\"\n").is_err());
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn missing_file_means_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Config::load(&dir.path().join("config.toml")).unwrap(), Config::default());
+    }
+}
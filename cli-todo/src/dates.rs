@@ -0,0 +1,111 @@
+use cli_todo::TodoError;
+use std::str::FromStr;
+
+/// A `--due` value: a calendar date or an offset from now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum When {
+    /// `YYYY-MM-DD`, midnight UTC.
+    At(u64),
+    /// `30m`, `2h`, `3d` or `1w` from now.
+    In(u64),
+}
+
+impl When {
+    /// The due time in epoch seconds, or an error if an offset from `now`
+    /// runs past the end of time.
+    pub fn resolve(self, now: u64) -> Result<u64, TodoError> {
+        match self {
+            When::At(time) => Ok(time),
+            When::In(secs) => now.checked_add(secs).ok_or(TodoError::DueOutOfRange(secs)),
+        }
+    }
+}
+
+impl FromStr for When {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(time) = epoch_from_date(s) {
+            return Ok(When::At(time));
+        }
+
+        let invalid = || format!("invalid due date '{}', expected YYYY-MM-DD or e.g. 30m, 2h, 3d, 1w", s);
+        let unit_len = s.chars().last().ok_or_else(invalid)?.len_utf8();
+        let (count, unit) = s.split_at(s.len() - unit_len);
+        let count: u64 = count.parse().map_err(|_| invalid())?;
+        let unit = match unit {
+            "m" => 60,
+            "h" => 3_600,
+            "d" => 86_400,
+            "w" => 604_800,
+            _ => return Err(invalid()),
+        };
+        count.checked_mul(unit).map(When::In).ok_or_else(invalid)
+    }
+}
+
+/// Formats epoch seconds as a UTC `YYYY-MM-DD` date.
+pub fn date_from_epoch(secs: u64) -> String {
+    // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Parses a `YYYY-MM-DD` date into epoch seconds at UTC midnight.
+pub fn epoch_from_date(date: &str) -> Option<u64> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next().filter(|p| p.len() == 4)?.parse().ok()?;
+    let month: i64 = parts.next().filter(|p| p.len() == 2)?.parse().ok()?;
+    let day: i64 = parts.next().filter(|p| p.len() == 2)?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days-from-civil, the inverse of `date_from_epoch`.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        assert_eq!(date_from_epoch(0), "1970-01-01");
+        assert_eq!(date_from_epoch(951_782_400), "2000-02-29");
+        assert_eq!(epoch_from_date("2000-02-29"), Some(951_782_400));
+        assert_eq!(epoch_from_date("2000-13-01"), None);
+        assert_eq!(epoch_from_date("tomorrow"), None);
+    }
+
+    #[test]
+    fn parses_due_dates_and_offsets() {
+        assert_eq!("2000-02-29".parse(), Ok(When::At(951_782_400)));
+        assert_eq!("90m".parse::<When>().unwrap().resolve(10).unwrap(), 10 + 90 * 60);
+        assert_eq!("2d".parse(), Ok(When::In(2 * 86_400)));
+        assert!("soon".parse::<When>().is_err());
+        assert!("3y".parse::<When>().is_err());
+    }
+
+    #[test]
+    fn rejects_offsets_that_overflow() {
+        assert!("99999999999999999w".parse::<When>().is_err());
+        let far = When::In(u64::MAX - 5);
+        assert!(matches!(far.resolve(10), Err(TodoError::DueOutOfRange(_))));
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+use std::io;
+use std::process::ExitCode;
+
+/// Everything that can go wrong while running a command.
+///
+/// Each variant belongs to an error class with its own exit code so scripts
+/// can tell a broken data file apart from a plain I/O failure.
+#[derive(Debug)]
+pub enum TodoError {
+    /// Reading, creating or writing the data file failed.
+    Io { path: String, source: io::Error },
+    /// The data file exists but its contents cannot be decoded.
+    Corrupt { path: String, source: io::Error },
+    /// The queue could not be encoded before writing it out.
+    Serialize(io::Error),
+    /// A file given to `import` could not be parsed.
+    Import { path: String, message: String },
+    /// No `--format` was given and the file extension did not name one.
+    UnknownFormat(String),
+    /// The data file was written by a newer version of this tool.
+    Unsupported { path: String, source: io::Error },
+    /// No task with this id exists in any list.
+    TaskNotFound(u64),
+    /// The config file exists but is not valid.
+    Config { path: String, message: String },
+    /// A `--due` offset of this many seconds lands past the largest time we
+    /// can store.
+    DueOutOfRange(u64),
+}
+
+impl TodoError {
+    /// Exit code for this error's class, following the BSD `sysexits.h` values.
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            TodoError::UnknownFormat(_) | TodoError::TaskNotFound(_) | TodoError::DueOutOfRange(_) => {
+                ExitCode::from(64)
+            }
+            TodoError::Corrupt { .. } | TodoError::Import { .. } => ExitCode::from(65),
+            TodoError::Unsupported { .. } => ExitCode::from(76),
+            TodoError::Config { .. } => ExitCode::from(78),
+            TodoError::Serialize(_) => ExitCode::from(70),
+            TodoError::Io { .. } => ExitCode::from(74),
+        }
+    }
+
+    /// A follow-up suggestion printed after the error message, if any.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            TodoError::Corrupt { .. } => {
+                Some("run `cli-todo repair` to back up the file and start a fresh list")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TodoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TodoError::Io { path, source } => write!(f, "could not access {}: {}", path, source),
+            TodoError::Corrupt { path, source } => {
+                write!(f, "{} is not a valid todo file: {}", path, source)
+            }
+            TodoError::Serialize(source) => write!(f, "could not encode the task list: {}", source),
+            TodoError::Import { path, message } => write!(f, "could not import {}: {}", path, message),
+            TodoError::UnknownFormat(path) => {
+                write!(f, "cannot tell the format of {}, pass --format", path)
+            }
+            TodoError::Unsupported { path, source } => write!(f, "cannot read {}: {}", path, source),
+            TodoError::TaskNotFound(id) => write!(f, "no task with id {}", id),
+            TodoError::Config { path, message } => write!(f, "invalid config {}: {}", path, message),
+            TodoError::DueOutOfRange(secs) => {
+                write!(f, "invalid due date, {}s from now is too far in the future", secs)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TodoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TodoError::Io { source, .. }
+            | TodoError::Corrupt { source, .. }
+            | TodoError::Unsupported { source, .. }
+            | TodoError::Serialize(source) => Some(source),
+            TodoError::Import { .. }
+            | TodoError::UnknownFormat(_)
+            | TodoError::TaskNotFound(_)
+            | TodoError::Config { .. }
+            | TodoError::DueOutOfRange(_) => None,
+        }
+    }
+}
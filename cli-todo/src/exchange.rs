@@ -0,0 +1,178 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::Todo;
+use crate::dates::{date_from_epoch, epoch_from_date};
+use crate::recurrence::Recurrence;
+
+/// Plain-text formats understood by `export` and `import`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExchangeFormat {
+    /// A JSON array of `{ "id", "title", "created_at", "due_at", "recurrence" }`
+    /// objects, with `null` for no due date or recurrence
+    Json,
+    /// One task per line in the todo.txt format, with the creation date
+    Todotxt,
+    /// A Markdown task list (`- [ ] title`)
+    Markdown,
+}
+
+impl ExchangeFormat {
+    /// Guesses the format from a file extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(ExchangeFormat::Json),
+            "txt" => Some(ExchangeFormat::Todotxt),
+            "md" | "markdown" => Some(ExchangeFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A task read from another format, before it is given an id in this queue.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct Entry {
+    pub title: String,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub due_at: Option<u64>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+}
+
+pub fn export(todos: &[&Todo], format: ExchangeFormat) -> String {
+    match format {
+        ExchangeFormat::Json => {
+            serde_json::to_string_pretty(todos).expect("todos always serialize") + "\n"
+        }
+        ExchangeFormat::Todotxt => todos.iter().map(|t| todotxt_line(t)).collect(),
+        ExchangeFormat::Markdown => todos.iter().map(|t| format!("- [ ] {}\n", t.title)).collect(),
+    }
+}
+
+/// Parses open tasks out of `input`. Completed tasks (`x ...` in todo.txt,
+/// `- [x]` in Markdown) are skipped since the queue only holds pending work.
+pub fn parse(input: &str, format: ExchangeFormat) -> Result<Vec<Entry>, String> {
+    match format {
+        ExchangeFormat::Json => serde_json::from_str(input).map_err(|e| e.to_string()),
+        ExchangeFormat::Todotxt => Ok(input.lines().filter_map(parse_todotxt_line).collect()),
+        ExchangeFormat::Markdown => Ok(input.lines().filter_map(parse_markdown_line).collect()),
+    }
+}
+
+fn todotxt_line(todo: &Todo) -> String {
+    let mut line = format!("{} {}", date_from_epoch(todo.created_at), todo.title);
+    if let Some(due) = todo.due_at {
+        line.push_str(&format!(" due:{}", date_from_epoch(due)));
+    }
+    if let Some(rule) = todo.recurrence {
+        line.push_str(&format!(" rec:{}", rule));
+    }
+    line.push_str(&format!(" id:{}\n", todo.id));
+    line
+}
+
+fn parse_todotxt_line(line: &str) -> Option<Entry> {
+    let mut rest = line.trim();
+    if rest.is_empty() || rest.starts_with("x ") {
+        return None;
+    }
+
+    // Optional priority, e.g. "(A) ".
+    let bytes = rest.as_bytes();
+    if bytes.len() > 4 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && &bytes[2..4] == b") " {
+        rest = &rest[4..];
+    }
+
+    let mut created_at = None;
+    if let Some((date, title)) = rest.split_once(' ')
+        && let Some(secs) = epoch_from_date(date)
+    {
+        created_at = Some(secs);
+        rest = title;
+    }
+
+    // `due:` and `rec:` tags map to fields. Our own `id:` tag is dropped;
+    // imported tasks get fresh ids.
+    let mut due_at = None;
+    let mut recurrence = None;
+    let mut words = Vec::new();
+    for word in rest.split_whitespace() {
+        if let Some(secs) = word.strip_prefix("due:").and_then(epoch_from_date) {
+            due_at = Some(secs);
+        } else if let Some(rule) = word.strip_prefix("rec:").and_then(|r| r.parse().ok()) {
+            recurrence = Some(rule);
+        } else if !word.starts_with("id:") {
+            words.push(word);
+        }
+    }
+    let title = words.join(" ");
+
+    (!title.is_empty()).then_some(Entry { title, created_at, due_at, recurrence })
+}
+
+fn parse_markdown_line(line: &str) -> Option<Entry> {
+    let rest = line.trim_start();
+    let rest = rest.strip_prefix("- ").or_else(|| rest.strip_prefix("* "))?;
+    let title = rest.strip_prefix("[ ] ")?.trim();
+
+    (!title.is_empty()).then(|| Entry {
+        title: title.to_string(),
+        created_at: None,
+        due_at: None,
+        recurrence: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn todotxt_round_trips_through_export() {
+        let todo = Todo {
+            id: 7,
+            title: "call mum +family".to_string(),
+            created_at: 951_782_400,
+            due_at: Some(951_868_800),
+            recurrence: Some(Recurrence::Weeks(1)),
+        };
+        let text = export(&[&todo], ExchangeFormat::Todotxt);
+        assert_eq!(text, "2000-02-29 call mum +family due:2000-03-01 rec:1w id:7\n");
+
+        let entries = parse(&text, ExchangeFormat::Todotxt).unwrap();
+        assert_eq!(
+            entries,
+            vec![Entry {
+                title: "call mum +family".to_string(),
+                created_at: Some(951_782_400),
+                due_at: Some(951_868_800),
+                recurrence: Some(Recurrence::Weeks(1)),
+            }]
+        );
+    }
+
+    #[test]
+    fn todotxt_skips_done_and_strips_priority() {
+        let input = "x 2024-01-02 done already\n(B) 2024-01-01 pay rent\n\nno date here\n";
+        let entries = parse(input, ExchangeFormat::Todotxt).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "pay rent");
+        assert!(entries[0].created_at.is_some());
+        assert_eq!(entries[1].title, "no date here");
+        assert_eq!(entries[1].created_at, None);
+    }
+
+    #[test]
+    fn markdown_reads_open_items_only() {
+        let input = "# Groceries\n- [ ] milk\n- [x] bread\n* [ ] eggs\nsome prose\n";
+        let titles: Vec<_> = parse(input, ExchangeFormat::Markdown)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.title)
+            .collect();
+        assert_eq!(titles, ["milk", "eggs"]);
+    }
+}
@@ -0,0 +1,10 @@
+//! The FIFO queue behind `cli-todo` and the storage backends it can be
+//! persisted with. The binary builds its named lists on top of these.
+
+pub mod error;
+pub mod queue;
+pub mod storage;
+
+pub use error::TodoError;
+pub use queue::Queue;
+pub use storage::{Encode, Storage};
@@ -1,175 +1,396 @@
 use clap::*;
+use clap_complete::Shell;
 use borsh::{BorshSerialize, BorshDeserialize};
-use std::collections::VecDeque;
-use std::io::Write;
+use cli_todo::storage::{BorshFile, JsonFile};
+use cli_todo::{Queue, Storage, TodoError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::time::SystemTime;
-use std::fs::File;
+use std::fs;
+use std::process::ExitCode;
 
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+use crate::archive::Completed;
+use crate::config::Config;
+use crate::dates::When;
+use crate::exchange::ExchangeFormat;
+use crate::output::Format;
+use crate::recurrence::Recurrence;
+use crate::store::{DEFAULT_LIST, Store};
+
+mod archive;
+mod config;
+mod dates;
+mod exchange;
+mod output;
+mod recurrence;
+mod store;
+mod tui;
+mod watch;
+
+#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 struct Todo {
     id: u64,
     title: String,
-    created_at: u64,      
+    created_at: u64,
+    due_at: Option<u64>,
+    recurrence: Option<Recurrence>,
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "cli-todo", version = "0.1.0", author = "Vedansh", about = "A first-in, first-out todo list for the terminal")]
+pub struct Args {
+    /// The named list to work on [default: default]
+    #[arg(long, global = true)]
+    list: Option<String>,
+
+    /// How the data file is stored [default: borsh]
+    #[arg(long, global = true, value_enum)]
+    backend: Option<Backend>,
+
+    /// Path of the data file [default: todo.bin, todo.json or todo.db]
+    #[arg(long, global = true)]
+    file: Option<PathBuf>,
+
+    /// Read defaults from this file instead of ~/.config/cli-todo/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Commands,
 }
 
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct Queue<T> {
-    items: VecDeque<T>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Compact binary file (the original format)
+    Borsh,
+    /// Pretty-printed JSON, easy to diff and keep in git
+    Json,
+    /// SQLite database
+    #[cfg(feature = "sqlite")]
+    Sqlite,
 }
 
-impl<T> Queue<T>
-where T : BorshSerialize + BorshDeserialize + std::fmt::Debug
-{
-    fn new() -> Self {
-        Queue {
-            items: VecDeque::new(),
+impl Backend {
+    fn default_file(&self) -> &'static str {
+        match self {
+            Backend::Borsh => "todo.bin",
+            Backend::Json => "todo.json",
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => "todo.db",
         }
     }
 
-    fn load() -> Self {
-        let mut file = match File::open("todo.bin") {
-            Ok(file) => file,
-            Err(_) => {
-                println!("Creating new file");
-                match File::create("todo.bin") {
-                    Ok(file) => file,
-                    Err(_) => {
-                        println!("Failed to create file");
-                        std::process::exit(1);
-                    }
-                }
-            }
-        };
-
-        match Queue::try_from_reader(&mut file) {
-            Ok(queue) => queue,
-            Err(_) => {
-                println!("Error deserializing file");
-                std::process::exit(1);
-            }
+    fn open(&self, file: Option<PathBuf>) -> Box<dyn Storage<Store>> {
+        let path = file.unwrap_or_else(|| PathBuf::from(self.default_file()));
+        match self {
+            Backend::Borsh => Box::new(BorshFile::new(path)),
+            Backend::Json => Box::new(JsonFile::new(path)),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => Box::new(cli_todo::storage::SqliteFile::new(path)),
         }
     }
+}
 
-    fn save(&self) {
-        let mut writer = Vec::new();
-        match self.serialize(&mut writer) {
-            Ok(_) => {},
-            Err(_) => {
-                println!("Error serializing queue");
-                std::process::exit(1);
-            }
-        }
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Add a task to the list
+    Add {
+        /// The task to add
+        title: String,
+        /// Repeat the task: `1d`, `2w`, `weekday`, ...
+        #[arg(long, value_name = "INTERVAL")]
+        every: Option<Recurrence>,
+        /// When the task is due: `YYYY-MM-DD`, or `30m`, `2h`, `3d`, `1w` from now
+        #[arg(long, value_name = "WHEN")]
+        due: Option<When>,
+    },
+    /// List the tasks, oldest first
+    List {
+        /// Output format [default: table]
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// Shorthand for `--format json`
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        /// Show at most this many tasks
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Only show tasks whose title contains this text (case-insensitive)
+        #[arg(long)]
+        search: Option<String>,
+    },
+    /// Complete the oldest task
+    Complete,
+    /// Show every list and how many tasks it holds
+    Lists,
+    /// Show completed tasks, most recent first
+    History {
+        /// Show at most this many tasks
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Summarise completed tasks: count per day and average time to finish
+    Stats {
+        /// How many days of per-day counts to show
+        #[arg(long, default_value_t = 7)]
+        days: u64,
+    },
+    /// Move a task to the back of another list
+    Move {
+        /// Id of the task to move
+        id: u64,
+        /// Name of the list to move it to
+        #[arg(long)]
+        to: String,
+    },
+    /// Back up an unreadable data file and start a fresh list
+    Repair,
+    /// Browse, add, edit and complete tasks in an interactive terminal UI
+    Tui,
+    /// Stay in the foreground and report tasks as they come due
+    Watch {
+        /// Check once for tasks that came due in the last `--interval` seconds, then exit
+        #[arg(long)]
+        once: bool,
+        /// Seconds between checks
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Shell command to run for each due task; the title is passed as `$1`
+        #[arg(long, value_name = "COMMAND")]
+        exec: Option<String>,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// The shell to generate completions for
+        shell: Shell,
+    },
+    /// Print the man page in roff format
+    Man,
+    /// Write all tasks in a format other tools can read
+    Export {
+        /// Output format
+        #[arg(long, value_enum)]
+        format: ExchangeFormat,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Append tasks from a JSON, todo.txt or Markdown file
+    Import {
+        /// The file to read
+        file: String,
+        /// Input format; guessed from the file extension when omitted
+        #[arg(long, value_enum)]
+        format: Option<ExchangeFormat>,
+    },
+}
 
-        let mut file = match File::create("todo.bin") {
-            Ok(file) => file,
-            Err(_) => {
-                println!("Failed to open file for writing");
-                std::process::exit(1);
-            }
-        };
+fn now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn main() -> ExitCode {
+
+    let args = Args::parse();
 
-        match file.write_all(&writer) {
-            Ok(_) => println!("Written successfully"),
-            Err(_) => {
-                println!("Error writing to file");
-                std::process::exit(1);
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            if let Some(hint) = e.hint() {
+                eprintln!("hint: {}", hint);
             }
+            e.exit_code()
         }
     }
+}
 
-    fn len(&self) -> u64 {
-        self.items.len() as u64
-    }
+fn save(storage: &mut dyn Storage<Store>, store: &Store) -> Result<(), TodoError> {
+    storage.save(store)?;
+    println!("Written successfully");
+    Ok(())
+}
 
-    fn enqueue(&mut self, item: T) {
-        self.items.push_back(item);
+fn run(args: Args) -> Result<(), TodoError> {
+    match args.command {
+        Commands::Completions { shell } => {
+            clap_complete::generate(shell, &mut Args::command(), "cli-todo", &mut std::io::stdout());
+            return Ok(());
+        }
+        Commands::Man => {
+            clap_mangen::Man::new(Args::command())
+                .render(&mut std::io::stdout())
+                .map_err(|e| TodoError::Io { path: "stdout".to_string(), source: e })?;
+            return Ok(());
+        }
+        _ => {}
     }
 
-    fn dequeue(&mut self) -> Option<T> {
-        println!("Dequeuing item");
-        match self.items.pop_front() {
-            Some(item) => Some(item),
-            None => {
-                println!("No task to complete");
-                None
-            }
+    let config = match args.config.or_else(Config::default_path) {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+
+    let backend = args.backend.or(config.backend).unwrap_or(Backend::Borsh);
+    let mut storage = backend.open(args.file.or(config.file));
+    let storage = storage.as_mut();
+    let location = storage.path().map(|p| p.display().to_string()).unwrap_or_default();
+
+    if let Commands::Repair = args.command {
+        match Store::repair(storage)? {
+            Some(backup) => println!("Moved unreadable {} to {} and started a new list", location, backup),
+            None => println!("{} is readable, nothing to repair", location),
         }
+        return Ok(());
     }
 
-    fn peek(&self) -> Option<&T> {
-        self.items.front()
+    if let Commands::Watch { once, interval, exec } = args.command {
+        let exec = exec.or(config.notify);
+        return watch::run(storage, exec.as_deref(), interval, once);
     }
 
-    fn is_empty(&self) -> bool {
-        self.items.is_empty()
-    }
+    let mut store = Store::load(storage)?;
+    let empty = Queue::new();
+    // `history` and `stats` cover every list unless one is named.
+    let only_list = args.list.clone();
+    let list = args.list.or(config.list).unwrap_or_else(|| DEFAULT_LIST.to_string());
 
-    fn clear(&mut self) {
-        self.items.clear();
-    }
+    match args.command {
+        Commands::Add { title, every, due } => {
+            println!("Adding todo: {}", title);
+
+            let created_at = now();
+            let due_at = match due {
+                Some(when) => Some(when.resolve(created_at)?),
+                None => every.map(|rule| rule.first_due(created_at)),
+            };
+            let todo = Todo {
+                id: store.next_id(),
+                title: title.clone(),
+                created_at,
+                due_at,
+                recurrence: every,
+            };
+
+            store.list_mut(&list).enqueue(todo);
+
+            save(storage, &store)?;
+        }
+        Commands::List { format, json, limit, search } => {
+            let format = if json { Format::Json } else { format.or(config.format).unwrap_or(Format::Table) };
+            let search = search.map(|s| s.to_lowercase());
+
+            let todos: Vec<&Todo> = store
+                .list(&list)
+                .unwrap_or(&empty)
+                .iter()
+                .filter(|t| search.as_ref().is_none_or(|s| t.title.to_lowercase().contains(s)))
+                .take(limit.unwrap_or(usize::MAX))
+                .collect();
+
+            print!("{}", output::render(&todos, format, now()));
+        }
+        Commands::Complete => {
+            let Some((done, next)) = store.complete(&list, now()) else {
+                println!("No task to complete");
+                return Ok(());
+            };
 
-    fn print(&self) {
-        if self.items.len() == 0 {
-            println!("No tasks to list");
-        } else {
-            for item in &self.items {
-                println!("{:?}", item);
+            println!("Completed '{}'", done.todo.title);
+            if let Some(id) = next {
+                println!("Next '{}' queued as task {}", done.todo.title, id);
             }
+            save(storage, &store)?;
         }
-    }
-}
+        Commands::History { limit, json } => {
+            let entries: Vec<&Completed> = store
+                .archive()
+                .iter()
+                .rev()
+                .filter(|c| only_list.as_ref().is_none_or(|l| &c.list == l))
+                .take(limit.unwrap_or(usize::MAX))
+                .collect();
 
-impl Queue<Todo> {
-    fn next_id(&self) -> u64 {
-        self.peek()
-            .map(|t| t.id + self.len())
-            .unwrap_or(1)
-    }
-}
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries).expect("history always serializes"));
+            } else {
+                print!("{}", archive::render_history(&entries, now()));
+            }
+        }
+        Commands::Stats { days } => {
+            let entries: Vec<&Completed> = store
+                .archive()
+                .iter()
+                .filter(|c| only_list.as_ref().is_none_or(|l| &c.list == l))
+                .collect();
 
-#[derive(Parser, Debug)]
-#[command(name = "cli-todo", version = "0.1.0", author = "Vedansh")]
-pub struct Args {
-    #[command(subcommand)]
-    command: Commands,
-}
+            print!("{}", archive::render_stats(&entries, now(), days));
+        }
+        Commands::Lists => {
+            let lists: Vec<_> = store.lists().collect();
+            if lists.is_empty() {
+                println!("No lists yet");
+            }
+            let width = lists.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+            for (name, queue) in lists {
+                println!("{:<width$}  {}", name, queue.len(), width = width);
+            }
+        }
+        Commands::Move { id, to } => {
+            store.move_task(id, &to)?;
+            println!("Moved task {} to {}", id, to);
+            save(storage, &store)?;
+        }
+        Commands::Export { format, output } => {
+            let todos: Vec<&Todo> = store.list(&list).unwrap_or(&empty).iter().collect();
+            let text = exchange::export(&todos, format);
 
-#[derive(Subcommand, Debug)]
-pub enum Commands {
-    /// Add a task to the list
-    Add {
-        /// The task to add
-        title: String
-    },
-    /// List all the tasks
-    List,
-    /// Complete the oldest task
-    Complete,
-}
+            match output {
+                Some(path) => fs::write(&path, text).map_err(|e| TodoError::Io { path, source: e })?,
+                None => print!("{}", text),
+            }
+        }
+        Commands::Import { file, format } => {
+            let format = format
+                .or_else(|| ExchangeFormat::from_path(&file))
+                .ok_or_else(|| TodoError::UnknownFormat(file.clone()))?;
 
-fn main() {
+            let input = fs::read_to_string(&file)
+                .map_err(|e| TodoError::Io { path: file.clone(), source: e })?;
+            let entries = exchange::parse(&input, format)
+                .map_err(|message| TodoError::Import { path: file.clone(), message })?;
 
-    let args = Args::parse();
+            let count = entries.len();
+            let created_at = now();
+            for entry in entries {
+                let todo = Todo {
+                    id: store.next_id(),
+                    title: entry.title,
+                    created_at: entry.created_at.unwrap_or(created_at),
+                    due_at: entry.due_at,
+                    recurrence: entry.recurrence,
+                };
+                store.list_mut(&list).enqueue(todo);
+            }
 
-    let mut queue = Queue::load();
-
-    if let Commands::Add { title } = args.command {
-        println!("Adding todo: {}", title);
-        
-        let todo = Todo {
-            id: queue.next_id(),
-            title: title.clone(),
-            created_at: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs(),
-        };
-
-        queue.enqueue(todo);
-
-        queue.save();
-        
-    } else if let Commands::List = args.command {
-        queue.print()
-    } else if let Commands::Complete = args.command {
-        queue.dequeue();
-        queue.save();
+            println!("Imported {} tasks from {}", count, file);
+            save(storage, &store)?;
+        }
+        Commands::Tui => {
+            let mut app = tui::App::new(store, list);
+            let mut terminal = ratatui::init();
+            let result = tui::run(&mut terminal, &mut app, tui::terminal_keys(), |store| storage.save(store));
+            ratatui::restore();
+            result?;
+        }
+        Commands::Repair | Commands::Watch { .. } | Commands::Completions { .. } | Commands::Man => {
+            unreachable!("handled before loading")
+        }
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
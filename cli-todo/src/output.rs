@@ -0,0 +1,184 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::Todo;
+
+/// How `list` renders the selected tasks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Aligned columns with relative ages, for people
+    Table,
+    /// A JSON array of tasks, for scripts
+    Json,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+}
+
+pub fn render(todos: &[&Todo], format: Format, now: u64) -> String {
+    match format {
+        Format::Table => render_table(todos, now),
+        Format::Json => serde_json::to_string_pretty(todos).expect("todos always serialize") + "\n",
+        Format::Csv => render_csv(todos),
+    }
+}
+
+fn render_table(todos: &[&Todo], now: u64) -> String {
+    if todos.is_empty() {
+        return "No tasks to list\n".to_string();
+    }
+
+    // Due and recurrence columns only appear when some task uses them.
+    let show_due = todos.iter().any(|t| t.due_at.is_some());
+    let show_every = todos.iter().any(|t| t.recurrence.is_some());
+
+    let mut header = vec!["ID", "TITLE", "AGE"];
+    if show_due {
+        header.push("DUE");
+    }
+    if show_every {
+        header.push("EVERY");
+    }
+
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect::<Vec<_>>()];
+    for t in todos {
+        let mut row = vec![t.id.to_string(), t.title.clone(), relative_age(now, t.created_at)];
+        if show_due {
+            row.push(t.due_at.map(|due| relative_due(now, due)).unwrap_or_default());
+        }
+        if show_every {
+            row.push(t.recurrence.map(|rule| rule.to_string()).unwrap_or_default());
+        }
+        rows.push(row);
+    }
+
+    let mut widths = vec![0; header.len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in rows {
+        let mut line = format!("{:>width$}", row[0], width = widths[0]);
+        for (cell, width) in row.iter().zip(&widths).skip(1) {
+            line.push_str(&format!("  {:<width$}", cell, width = width));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn render_csv(todos: &[&Todo]) -> String {
+    let mut out = String::from("id,title,created_at,due_at,recurrence\n");
+    for t in todos {
+        out.push_str(&format!(
+            "{},{},{},{},{}\n",
+            t.id,
+            csv_field(&t.title),
+            t.created_at,
+            t.due_at.map(|d| d.to_string()).unwrap_or_default(),
+            t.recurrence.map(|r| r.to_string()).unwrap_or_default(),
+        ));
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats the time elapsed since `then` as a short phrase such as "3h ago".
+pub fn relative_age(now: u64, then: u64) -> String {
+    let secs = now.saturating_sub(then);
+    if secs < 60 {
+        "just now".to_string()
+    } else {
+        format!("{} ago", span(secs))
+    }
+}
+
+/// Like [`relative_age`], but also handles future times as "in 3h".
+pub fn relative_due(now: u64, due: u64) -> String {
+    if due > now + 59 {
+        format!("in {}", span(due - now))
+    } else {
+        relative_age(now, due)
+    }
+}
+
+/// A coarse duration such as "3h" or "2w".
+pub fn span(secs: u64) -> String {
+    match secs {
+        0..60 => "<1m".to_string(),
+        60..3_600 => format!("{}m", secs / 60),
+        3_600..86_400 => format!("{}h", secs / 3_600),
+        86_400..604_800 => format!("{}d", secs / 86_400),
+        _ => format!("{}w", secs / 604_800),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::Recurrence;
+
+    fn todo(id: u64, title: &str, created_at: u64) -> Todo {
+        Todo { id, title: title.to_string(), created_at, ..Default::default() }
+    }
+
+    #[test]
+    fn relative_age_buckets() {
+        assert_eq!(relative_age(100, 100), "just now");
+        assert_eq!(relative_age(100, 200), "just now");
+        assert_eq!(relative_age(3 * 60, 0), "3m ago");
+        assert_eq!(relative_age(3 * 3_600 + 59, 0), "3h ago");
+        assert_eq!(relative_age(2 * 86_400, 0), "2d ago");
+        assert_eq!(relative_age(15 * 86_400, 0), "2w ago");
+        assert_eq!(relative_due(0, 3 * 3_600), "in 3h");
+        assert_eq!(relative_due(3 * 3_600, 0), "3h ago");
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let a = todo(1, "short", 0);
+        let b = todo(12, "a longer title", 7_200);
+        let out = render(&[&a, &b], Format::Table, 7_200);
+        assert_eq!(
+            out,
+            "ID  TITLE           AGE\n 1  short           2h ago\n12  a longer title  just now\n"
+        );
+    }
+
+    #[test]
+    fn table_shows_due_and_recurrence_when_present() {
+        let a = todo(1, "plain", 0);
+        let b = Todo {
+            due_at: Some(86_400),
+            recurrence: Some(Recurrence::Days(1)),
+            ..todo(2, "water plants", 0)
+        };
+        let out = render(&[&a, &b], Format::Table, 3_600);
+        assert_eq!(
+            out,
+            "ID  TITLE         AGE     DUE     EVERY\n 1  plain         1h ago\n 2  water plants  1h ago  in 23h  1d\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_awkward_titles() {
+        let a = todo(1, "milk, eggs", 5);
+        let b = todo(2, "say \"hi\"", 6);
+        let out = render(&[&a, &b], Format::Csv, 0);
+        assert_eq!(
+            out,
+            "id,title,created_at,due_at,recurrence\n1,\"milk, eggs\",5,,\n2,\"say \"\"hi\"\"\",6,,\n"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+
+use crate::storage::Encode;
+
+/// A first-in, first-out queue that serializes as a plain sequence.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Queue<T> {
+    items: VecDeque<T>,
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Queue::new()
+    }
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Queue {
+            items: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.items.len() as u64
+    }
+
+    pub fn enqueue(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.items.pop_front()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.items.iter_mut()
+    }
+
+    /// Takes the first item matching `pred` out of the queue, wherever it sits.
+    pub fn remove_where(&mut self, pred: impl Fn(&T) -> bool) -> Option<T> {
+        let index = self.items.iter().position(pred)?;
+        self.items.remove(index)
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Queue {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> IntoIterator for Queue<T> {
+    type Item = T;
+    type IntoIter = std::collections::vec_deque::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+/// A bare queue is stored as plain borsh, with no header.
+impl<T: BorshSerialize + BorshDeserialize> Encode for Queue<T> {
+    fn encode(&self) -> io::Result<Vec<u8>> {
+        borsh::to_vec(self)
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        borsh::from_slice(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_order() {
+        let mut queue: Queue<u32> = (1..=3).collect();
+        queue.enqueue(4);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.dequeue(), Some(1));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.into_iter().collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn remove_where_keeps_the_rest_in_order() {
+        let mut queue: Queue<u32> = (1..=4).collect();
+        assert_eq!(queue.remove_where(|&n| n % 2 == 0), Some(2));
+        assert_eq!(queue.remove_where(|&n| n > 10), None);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [1, 3, 4]);
+    }
+}
@@ -0,0 +1,153 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+const DAY: u64 = 86_400;
+
+/// How often a recurring task comes back after it is completed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum Recurrence {
+    /// Every `n` days, written `nd`.
+    Days(u32),
+    /// Every `n` weeks, written `nw`.
+    Weeks(u32),
+    /// Monday to Friday, written `weekday`.
+    Weekdays,
+}
+
+impl Recurrence {
+    /// First due date for a task created at `now`: right away, unless a
+    /// weekday task is added at the weekend.
+    pub fn first_due(&self, now: u64) -> u64 {
+        match self {
+            Recurrence::Weekdays if !is_weekday(now) => next_weekday(now).unwrap_or(u64::MAX),
+            _ => now,
+        }
+    }
+
+    /// Due date of the occurrence after one due at `due`.
+    ///
+    /// Occurrences missed while the task sat undone are skipped, so the result
+    /// is always after `now` and finishing a chore late does not queue a
+    /// backlog of copies. A due date too large for a `u64` saturates to
+    /// `u64::MAX`.
+    pub fn next_due(&self, due: u64, now: u64) -> u64 {
+        let next = match *self {
+            Recurrence::Days(n) => skip(due, now, u64::from(n).checked_mul(DAY)),
+            Recurrence::Weeks(n) => skip(due, now, u64::from(n).checked_mul(7 * DAY)),
+            Recurrence::Weekdays => next_weekday(due.max(now)),
+        };
+        next.unwrap_or(u64::MAX)
+    }
+}
+
+/// The first `due + k * step`, for `k >= 1`, that is after `now`.
+fn skip(due: u64, now: u64, step: Option<u64>) -> Option<u64> {
+    let step = step?;
+    let missed = now.saturating_sub(due) / step;
+    (missed + 1).checked_mul(step).and_then(|ahead| due.checked_add(ahead))
+}
+
+fn is_weekday(time: u64) -> bool {
+    // 1970-01-01 was a Thursday, so (day + 3) % 7 is 0 for Monday.
+    (time / DAY + 3) % 7 < 5
+}
+
+/// The first Monday-to-Friday day strictly after `time`, at midnight UTC, or
+/// `None` if that does not fit in a `u64`.
+fn next_weekday(time: u64) -> Option<u64> {
+    let mut next = (time / DAY + 1).checked_mul(DAY)?;
+    while !is_weekday(next) {
+        next = next.checked_add(DAY)?;
+    }
+    Some(next)
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("weekday") || s.eq_ignore_ascii_case("weekdays") {
+            return Ok(Recurrence::Weekdays);
+        }
+
+        let invalid = || format!("invalid interval '{}', expected e.g. 1d, 2w or weekday", s);
+        let unit_len = s.chars().last().ok_or_else(invalid)?.len_utf8();
+        let (count, unit) = s.split_at(s.len() - unit_len);
+        let count: u32 = count.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?;
+        match unit {
+            "d" => Ok(Recurrence::Days(count)),
+            "w" => Ok(Recurrence::Weeks(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Days(n) => write!(f, "{}d", n),
+            Recurrence::Weeks(n) => write!(f, "{}w", n),
+            Recurrence::Weekdays => write!(f, "weekday"),
+        }
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as Deserialize>::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-05 was a Friday.
+    const FRIDAY: u64 = 1_704_412_800;
+
+    #[test]
+    fn parses_and_prints() {
+        for text in ["1d", "3d", "1w", "2w", "weekday"] {
+            assert_eq!(text.parse::<Recurrence>().unwrap().to_string(), text);
+        }
+        assert!("0d".parse::<Recurrence>().is_err());
+        assert!("d".parse::<Recurrence>().is_err());
+        assert!("".parse::<Recurrence>().is_err());
+        assert!("1y".parse::<Recurrence>().is_err());
+        assert!("1日".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn weekdays_skip_the_weekend() {
+        let rule = Recurrence::Weekdays;
+        assert_eq!(rule.next_due(FRIDAY, FRIDAY), FRIDAY + 3 * DAY);
+        assert_eq!(rule.first_due(FRIDAY + DAY + 3_600), FRIDAY + 3 * DAY);
+        assert_eq!(rule.first_due(FRIDAY + 3_600), FRIDAY + 3_600);
+    }
+
+    #[test]
+    fn next_due_skips_missed_occurrences() {
+        let rule = Recurrence::Days(1);
+        assert_eq!(rule.next_due(FRIDAY, FRIDAY + 10), FRIDAY + DAY);
+        assert_eq!(rule.next_due(FRIDAY, FRIDAY + 3 * DAY + 10), FRIDAY + 4 * DAY);
+        assert_eq!(Recurrence::Weeks(2).next_due(FRIDAY, FRIDAY), FRIDAY + 14 * DAY);
+    }
+
+    #[test]
+    fn next_due_saturates_instead_of_overflowing() {
+        let late = u64::MAX - DAY;
+        assert_eq!(Recurrence::Days(u32::MAX).next_due(late, late), u64::MAX);
+        assert_eq!(Recurrence::Weeks(u32::MAX).next_due(FRIDAY, FRIDAY), FRIDAY + u64::from(u32::MAX) * 7 * DAY);
+        assert_eq!(Recurrence::Weeks(u32::MAX).next_due(late, late), u64::MAX);
+        assert_eq!(Recurrence::Weekdays.next_due(u64::MAX, u64::MAX), u64::MAX);
+        assert_eq!(Recurrence::Days(1).next_due(FRIDAY, u64::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,367 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::error::TodoError;
+
+/// Where a value of type `T` is kept between runs.
+pub trait Storage<T> {
+    /// Reads the stored value, or `None` if nothing has been saved yet.
+    fn load(&mut self) -> Result<Option<T>, TodoError>;
+
+    fn save(&mut self, value: &T) -> Result<(), TodoError>;
+
+    /// The file behind this storage, if there is one. Used in messages and by
+    /// `repair` to move a broken file aside.
+    fn path(&self) -> Option<&Path>;
+
+    /// Lets go of anything still pointing at the file, so the next `load` or
+    /// `save` starts over at `path`. Called by `repair` after it moves the
+    /// file aside.
+    fn reset(&mut self) {}
+}
+
+/// Binary encoding used by the byte-oriented backends ([`BorshFile`] and
+/// `SqliteFile`). Types with several on-disk versions upgrade old bytes in
+/// `decode`, and report bytes from a newer version as
+/// [`ErrorKind::Unsupported`].
+pub trait Encode: Sized {
+    fn encode(&self) -> io::Result<Vec<u8>>;
+
+    fn decode(bytes: &[u8]) -> io::Result<Self>;
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// Sorts a decoding failure into "file is broken" or "file is too new".
+fn decode_error(path: &Path, source: io::Error) -> TodoError {
+    let path = display(path);
+    if source.kind() == ErrorKind::Unsupported {
+        TodoError::Unsupported { path, source }
+    } else {
+        TodoError::Corrupt { path, source }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>, TodoError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(TodoError::Io { path: display(path), source: e }),
+    }
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), TodoError> {
+    fs::write(path, bytes).map_err(|e| TodoError::Io { path: display(path), source: e })
+}
+
+/// Keeps the value in memory only, for tests.
+#[derive(Debug, Default)]
+pub struct Memory<T> {
+    value: Option<T>,
+}
+
+impl<T> Memory<T> {
+    pub fn new() -> Self {
+        Memory { value: None }
+    }
+
+    pub fn with(value: T) -> Self {
+        Memory { value: Some(value) }
+    }
+}
+
+impl<T: Clone> Storage<T> for Memory<T> {
+    fn load(&mut self) -> Result<Option<T>, TodoError> {
+        Ok(self.value.clone())
+    }
+
+    fn save(&mut self, value: &T) -> Result<(), TodoError> {
+        self.value = Some(value.clone());
+        Ok(())
+    }
+
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// A file holding the value's [`Encode`] bytes, borsh for the types in this
+/// crate. This is the original `todo.bin` format.
+pub struct BorshFile<T> {
+    path: PathBuf,
+    _value: PhantomData<T>,
+}
+
+impl<T> BorshFile<T> {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        BorshFile { path: path.into(), _value: PhantomData }
+    }
+}
+
+impl<T: Encode> Storage<T> for BorshFile<T> {
+    fn load(&mut self) -> Result<Option<T>, TodoError> {
+        let Some(bytes) = read_file(&self.path)? else {
+            return Ok(None);
+        };
+        T::decode(&bytes).map(Some).map_err(|e| decode_error(&self.path, e))
+    }
+
+    fn save(&mut self, value: &T) -> Result<(), TodoError> {
+        let bytes = value.encode().map_err(TodoError::Serialize)?;
+        write_file(&self.path, &bytes)
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+}
+
+/// Version of [`JsonFile`]'s envelope, bumped whenever the layout of the
+/// values written into it changes.
+const JSON_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct JsonEnvelope<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct JsonEnvelopeOwned {
+    version: u32,
+    data: serde_json::Value,
+}
+
+/// A human-readable JSON file, handy for keeping lists in git. The value is
+/// wrapped as `{"version": .., "data": ..}` so a later layout can be told
+/// apart from this one.
+pub struct JsonFile<T> {
+    path: PathBuf,
+    _value: PhantomData<T>,
+}
+
+impl<T> JsonFile<T> {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        JsonFile { path: path.into(), _value: PhantomData }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Storage<T> for JsonFile<T> {
+    fn load(&mut self) -> Result<Option<T>, TodoError> {
+        let Some(bytes) = read_file(&self.path)? else {
+            return Ok(None);
+        };
+        let envelope: JsonEnvelopeOwned =
+            serde_json::from_slice(&bytes).map_err(|e| decode_error(&self.path, e.into()))?;
+        if envelope.version > JSON_VERSION {
+            let source = io::Error::new(
+                ErrorKind::Unsupported,
+                format!("data file version {} is newer than this build supports", envelope.version),
+            );
+            return Err(decode_error(&self.path, source));
+        }
+        serde_json::from_value(envelope.data)
+            .map(Some)
+            .map_err(|e| decode_error(&self.path, e.into()))
+    }
+
+    fn save(&mut self, value: &T) -> Result<(), TodoError> {
+        let envelope = JsonEnvelope { version: JSON_VERSION, data: value };
+        let mut bytes = serde_json::to_vec_pretty(&envelope).map_err(|e| TodoError::Serialize(e.into()))?;
+        bytes.push(b'\n');
+        write_file(&self.path, &bytes)
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteFile;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use rusqlite::{Connection, ErrorCode, OptionalExtension, params};
+    use std::io;
+    use std::marker::PhantomData;
+    use std::path::{Path, PathBuf};
+
+    use super::{Encode, Storage, decode_error, display};
+    use crate::error::TodoError;
+
+    /// An SQLite database holding the value's [`Encode`] bytes in a single
+    /// row, so saves are atomic and the file survives interrupted writes.
+    pub struct SqliteFile<T> {
+        path: PathBuf,
+        connection: Option<Connection>,
+        _value: PhantomData<T>,
+    }
+
+    impl<T> SqliteFile<T> {
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            SqliteFile { path: path.into(), connection: None, _value: PhantomData }
+        }
+
+        /// A file that is not an SQLite database, or a row that does not
+        /// hold our blob, is corrupt and can be repaired; anything else is an
+        /// I/O failure.
+        fn error(&self, e: rusqlite::Error) -> TodoError {
+            let corrupt = match &e {
+                rusqlite::Error::SqliteFailure(failure, _) => {
+                    matches!(failure.code, ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt)
+                }
+                rusqlite::Error::FromSqlConversionFailure(..) | rusqlite::Error::InvalidColumnType(..) => true,
+                _ => false,
+            };
+            let path = display(&self.path);
+            let source = io::Error::other(e);
+            if corrupt {
+                TodoError::Corrupt { path, source }
+            } else {
+                TodoError::Io { path, source }
+            }
+        }
+
+        fn connection(&mut self) -> Result<&Connection, TodoError> {
+            if self.connection.is_none() {
+                let connection = Connection::open(&self.path).map_err(|e| self.error(e))?;
+                connection
+                    .execute(
+                        "CREATE TABLE IF NOT EXISTS store (
+                            id   INTEGER PRIMARY KEY CHECK (id = 1),
+                            body BLOB NOT NULL
+                        )",
+                        [],
+                    )
+                    .map_err(|e| self.error(e))?;
+                self.connection = Some(connection);
+            }
+            Ok(self.connection.as_ref().expect("opened above"))
+        }
+    }
+
+    impl<T: Encode> Storage<T> for SqliteFile<T> {
+        fn load(&mut self) -> Result<Option<T>, TodoError> {
+            let body: Option<Vec<u8>> = self
+                .connection()?
+                .query_row("SELECT body FROM store WHERE id = 1", [], |row| row.get(0))
+                .optional()
+                .map_err(|e| self.error(e))?;
+
+            match body {
+                Some(bytes) => T::decode(&bytes).map(Some).map_err(|e| decode_error(&self.path, e)),
+                None => Ok(None),
+            }
+        }
+
+        fn save(&mut self, value: &T) -> Result<(), TodoError> {
+            let bytes = value.encode().map_err(TodoError::Serialize)?;
+            let result = self.connection()?.execute(
+                "INSERT INTO store (id, body) VALUES (1, ?1)
+                 ON CONFLICT (id) DO UPDATE SET body = excluded.body",
+                params![bytes],
+            );
+            result.map(|_| ()).map_err(|e| self.error(e))
+        }
+
+        fn path(&self) -> Option<&Path> {
+            Some(&self.path)
+        }
+
+        fn reset(&mut self) {
+            // The open connection follows the file it was opened on, even
+            // after `repair` renames it.
+            self.connection = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Queue;
+
+    fn round_trip(storage: &mut dyn Storage<Queue<String>>) {
+        assert_eq!(storage.load().unwrap(), None);
+
+        let queue: Queue<String> = ["a", "b"].into_iter().map(String::from).collect();
+        storage.save(&queue).unwrap();
+        assert_eq!(storage.load().unwrap(), Some(queue));
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        round_trip(&mut Memory::new());
+    }
+
+    #[test]
+    fn borsh_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        round_trip(&mut BorshFile::new(dir.path().join("todo.bin")));
+    }
+
+    #[test]
+    fn json_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.json");
+        round_trip(&mut JsonFile::new(&path));
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "{\n  \"version\": 1,\n  \"data\": [\n    \"a\",\n    \"b\"\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn json_file_checks_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.json");
+
+        fs::write(&path, r#"{"version": 2, "data": []}"#).unwrap();
+        let err = JsonFile::<Queue<String>>::new(&path).load().unwrap_err();
+        assert!(matches!(err, TodoError::Unsupported { .. }));
+
+        fs::write(&path, r#"["a", "b"]"#).unwrap();
+        let err = JsonFile::<Queue<String>>::new(&path).load().unwrap_err();
+        assert!(matches!(err, TodoError::Corrupt { .. }));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.db");
+        round_trip(&mut SqliteFile::new(&path));
+
+        // A fresh handle sees what the first one saved.
+        let mut reopened = SqliteFile::<Queue<String>>::new(&path);
+        assert_eq!(reopened.load().unwrap().unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_file_that_is_not_a_database_is_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.db");
+        fs::write(&path, vec![b'x'; 4096]).unwrap();
+
+        let err = SqliteFile::<Queue<String>>::new(&path).load().unwrap_err();
+        assert!(matches!(err, TodoError::Corrupt { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn broken_file_is_reported_as_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.bin");
+        fs::write(&path, b"\xff").unwrap();
+
+        let err = BorshFile::<Queue<String>>::new(&path).load().unwrap_err();
+        assert!(matches!(err, TodoError::Corrupt { .. }));
+    }
+}
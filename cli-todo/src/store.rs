@@ -0,0 +1,355 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use cli_todo::{Encode, Queue, Storage, TodoError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+
+use crate::archive::Completed;
+use crate::{Todo, now};
+
+/// Marks a binary data file written by this version or later. Files without
+/// it hold a single bare `Queue<Todo>` and are migrated into the default list.
+const MAGIC: &[u8; 4] = b"TODO";
+/// Version 1 stored named lists, version 2 added due dates and recurrence,
+/// version 3 added the archive of completed tasks.
+const VERSION: u8 = 3;
+
+pub const DEFAULT_LIST: &str = "default";
+
+/// Every named list, kept in one file so ids stay unique across them.
+#[derive(Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct Store {
+    next_id: u64,
+    lists: BTreeMap<String, Queue<Todo>>,
+    archive: Vec<Completed>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store::new()
+    }
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Store {
+            next_id: 1,
+            lists: BTreeMap::new(),
+            archive: Vec::new(),
+        }
+    }
+
+    /// Reads the store, starting empty if nothing has been saved yet.
+    pub fn load(storage: &mut dyn Storage<Store>) -> Result<Self, TodoError> {
+        Ok(storage.load()?.unwrap_or_default())
+    }
+
+    fn from_legacy(queue: Queue<TodoV1>) -> Self {
+        let next_id = queue.iter().map(|t| t.id + 1).max().unwrap_or(1);
+        let mut lists = BTreeMap::new();
+        if !queue.is_empty() {
+            lists.insert(DEFAULT_LIST.to_string(), queue);
+        }
+        Store::from(StoreV2::from(StoreV1 { next_id, lists }))
+    }
+
+    /// Moves an unreadable data file aside and replaces it with an empty store.
+    ///
+    /// Returns the backup path, or `None` if the file was fine and left alone.
+    pub fn repair(storage: &mut dyn Storage<Store>) -> Result<Option<String>, TodoError> {
+        match storage.load() {
+            Ok(_) => return Ok(None),
+            Err(TodoError::Corrupt { .. }) => {}
+            Err(e) => return Err(e),
+        }
+
+        if let Some(path) = storage.path() {
+            let backup = format!("{}.{}.bak", path.display(), now());
+            fs::rename(path, &backup)
+                .map_err(|e| TodoError::Io { path: backup.clone(), source: e })?;
+            storage.reset();
+            storage.save(&Store::new())?;
+            return Ok(Some(backup));
+        }
+
+        storage.save(&Store::new())?;
+        Ok(None)
+    }
+
+    /// Hands out the next task id. Ids are never reused, even across lists.
+    pub fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub fn list(&self, name: &str) -> Option<&Queue<Todo>> {
+        self.lists.get(name)
+    }
+
+    /// Returns the named list, creating it if this is its first task.
+    pub fn list_mut(&mut self, name: &str) -> &mut Queue<Todo> {
+        self.lists.entry(name.to_string()).or_default()
+    }
+
+    pub fn lists(&self) -> impl Iterator<Item = (&str, &Queue<Todo>)> {
+        self.lists.iter().map(|(name, queue)| (name.as_str(), queue))
+    }
+
+    /// Moves a task to the back of another list, keeping its id.
+    pub fn move_task(&mut self, id: u64, to: &str) -> Result<(), TodoError> {
+        let todo = self
+            .lists
+            .values_mut()
+            .find_map(|queue| queue.remove_where(|t| t.id == id))
+            .ok_or(TodoError::TaskNotFound(id))?;
+
+        self.list_mut(to).enqueue(todo);
+        Ok(())
+    }
+
+    /// Completes the oldest task in `list`. Returns the archived task and, for
+    /// a recurring one, the id of its next occurrence.
+    pub fn complete(&mut self, list: &str, at: u64) -> Option<(&Completed, Option<u64>)> {
+        let todo = self.lists.get_mut(list)?.dequeue()?;
+        Some(self.finish(list, todo, at))
+    }
+
+    /// Archives a task already taken out of `list`, queueing its next
+    /// occurrence at the back of the same list if it recurs.
+    pub fn finish(&mut self, list: &str, todo: Todo, at: u64) -> (&Completed, Option<u64>) {
+        let mut next_id = None;
+        if let Some(rule) = todo.recurrence {
+            let next = Todo {
+                id: self.next_id(),
+                title: todo.title.clone(),
+                created_at: at,
+                due_at: Some(rule.next_due(todo.due_at.unwrap_or(todo.created_at), at)),
+                recurrence: Some(rule),
+            };
+            next_id = Some(next.id);
+            self.list_mut(list).enqueue(next);
+        }
+
+        self.archive.push(Completed { todo, list: list.to_string(), completed_at: at });
+        (self.archive.last().expect("just pushed"), next_id)
+    }
+
+    /// Completed tasks, oldest first.
+    pub fn archive(&self) -> &[Completed] {
+        &self.archive
+    }
+}
+
+impl Encode for Store {
+    fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut writer = MAGIC.to_vec();
+        writer.push(VERSION);
+        BorshSerialize::serialize(self, &mut writer)?;
+        Ok(writer)
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let Some(rest) = bytes.strip_prefix(MAGIC) else {
+            let legacy: Queue<TodoV1> = borsh::from_slice(bytes)?;
+            return Ok(Self::from_legacy(legacy));
+        };
+
+        match rest.split_first() {
+            Some((&VERSION, body)) => borsh::from_slice(body),
+            Some((2, body)) => borsh::from_slice::<StoreV2>(body).map(Store::from),
+            Some((1, body)) => borsh::from_slice::<StoreV1>(body).map(|old| Store::from(StoreV2::from(old))),
+            Some((&version, _)) => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("data file version {} is newer than this build supports", version),
+            )),
+            None => Err(ErrorKind::UnexpectedEof.into()),
+        }
+    }
+}
+
+/// A task as stored before due dates and recurrence existed.
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+struct TodoV1 {
+    id: u64,
+    title: String,
+    created_at: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct StoreV1 {
+    next_id: u64,
+    lists: BTreeMap<String, Queue<TodoV1>>,
+}
+
+/// The layout before completed tasks were archived.
+#[derive(BorshDeserialize)]
+struct StoreV2 {
+    next_id: u64,
+    lists: BTreeMap<String, Queue<Todo>>,
+}
+
+impl From<StoreV2> for Store {
+    fn from(old: StoreV2) -> Self {
+        Store { next_id: old.next_id, lists: old.lists, archive: Vec::new() }
+    }
+}
+
+impl From<StoreV1> for StoreV2 {
+    fn from(old: StoreV1) -> Self {
+        let lists = old
+            .lists
+            .into_iter()
+            .map(|(name, queue)| {
+                let upgraded = queue
+                    .into_iter()
+                    .map(|t| Todo {
+                        id: t.id,
+                        title: t.title,
+                        created_at: t.created_at,
+                        due_at: None,
+                        recurrence: None,
+                    })
+                    .collect();
+                (name, upgraded)
+            })
+            .collect();
+
+        StoreV2 { next_id: old.next_id, lists }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::Recurrence;
+    use cli_todo::storage::{BorshFile, Memory};
+
+    fn todo(id: u64, title: &str) -> Todo {
+        Todo { id, title: title.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn migrates_legacy_queue_into_default_list() {
+        let mut legacy = Queue::new();
+        legacy.enqueue(TodoV1 { id: 4, title: "old".to_string(), created_at: 0 });
+        legacy.enqueue(TodoV1 { id: 5, title: "older".to_string(), created_at: 0 });
+        let bytes = borsh::to_vec(&legacy).unwrap();
+
+        let mut storage = Memory::with(Store::decode(&bytes).unwrap());
+        let mut store = Store::load(&mut storage).unwrap();
+        let titles: Vec<_> = store.list(DEFAULT_LIST).unwrap().iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["old", "older"]);
+        assert_eq!(store.next_id(), 6);
+    }
+
+    #[test]
+    fn upgrades_version_one_files() {
+        let mut queue = Queue::new();
+        queue.enqueue(TodoV1 { id: 2, title: "chore".to_string(), created_at: 9 });
+        let mut old = BTreeMap::new();
+        old.insert("home".to_string(), queue);
+
+        let mut bytes = [MAGIC.as_slice(), &[1]].concat();
+        BorshSerialize::serialize(&(3u64, old), &mut bytes).unwrap();
+
+        let mut storage = Memory::with(Store::decode(&bytes).unwrap());
+        let store = Store::load(&mut storage).unwrap();
+        assert!(store.archive().is_empty());
+        let todo = store.list("home").unwrap().peek().unwrap();
+        assert_eq!((todo.id, todo.created_at, todo.due_at), (2, 9, None));
+        assert_eq!(store.next_id, 3);
+    }
+
+    #[test]
+    fn complete_archives_and_requeues_recurring_tasks() {
+        let mut storage = Memory::new();
+        let mut store = Store::load(&mut storage).unwrap();
+        let id = store.next_id();
+        let chore = Todo {
+            id,
+            title: "bins".to_string(),
+            due_at: Some(100),
+            recurrence: Some(Recurrence::Weeks(1)),
+            ..Default::default()
+        };
+        store.list_mut("home").enqueue(chore);
+
+        let (done, next) = store.complete("home", 200).unwrap();
+        assert_eq!((done.todo.id, done.completed_at, done.list.as_str()), (id, 200, "home"));
+        let next = next.unwrap();
+        storage.save(&store).unwrap();
+
+        let mut store = Store::load(&mut storage).unwrap();
+        let queued = store.list("home").unwrap().peek().unwrap();
+        assert_eq!((queued.id, queued.due_at), (next, Some(100 + 7 * 86_400)));
+        assert_eq!(store.archive().len(), 1);
+        assert!(store.complete("nowhere", 300).is_none());
+    }
+
+    #[test]
+    fn repair_moves_a_broken_file_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.bin");
+        fs::write(&path, b"not a todo file").unwrap();
+        let mut storage = BorshFile::new(&path);
+
+        let backup = Store::repair(&mut storage).unwrap().unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), b"not a todo file");
+        assert!(Store::load(&mut storage).unwrap().lists().next().is_none());
+
+        // A readable file is left alone.
+        assert_eq!(Store::repair(&mut storage).unwrap(), None);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn repair_replaces_a_broken_sqlite_file() {
+        use cli_todo::storage::SqliteFile;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.db");
+        let mut storage = SqliteFile::new(&path);
+        storage.save(&Store::new()).unwrap();
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute("UPDATE store SET body = x'ff'", [])
+            .unwrap();
+
+        // `load` opens the connection that has to be let go after the rename.
+        let backup = Store::repair(&mut storage).unwrap().unwrap();
+        let body: Vec<u8> = rusqlite::Connection::open(&backup)
+            .unwrap()
+            .query_row("SELECT body FROM store", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(body, [0xff]);
+        assert!(Store::load(&mut SqliteFile::new(&path)).unwrap().lists().next().is_none());
+        assert_eq!(Store::repair(&mut storage).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let bytes = [MAGIC.as_slice(), &[VERSION + 1]].concat();
+        assert_eq!(Store::decode(&bytes).err().map(|e| e.kind()), Some(ErrorKind::Unsupported));
+    }
+
+    #[test]
+    fn move_keeps_id_and_appends() {
+        let mut storage = Memory::new();
+        let mut store = Store::load(&mut storage).unwrap();
+        let a = store.next_id();
+        let b = store.next_id();
+        store.list_mut("work").enqueue(todo(a, "report"));
+        store.list_mut("personal").enqueue(todo(b, "gym"));
+
+        store.move_task(a, "personal").unwrap();
+        storage.save(&store).unwrap();
+
+        let mut store = Store::load(&mut storage).unwrap();
+        assert!(store.list("work").unwrap().is_empty());
+        let ids: Vec<_> = store.list("personal").unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(ids, [b, a]);
+        assert!(matches!(store.move_task(99, "work"), Err(TodoError::TaskNotFound(99))));
+    }
+}
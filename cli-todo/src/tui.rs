@@ -0,0 +1,388 @@
+use ratatui::Terminal;
+use ratatui::backend::Backend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use cli_todo::TodoError;
+
+use crate::output::{relative_age, relative_due};
+use crate::store::Store;
+use crate::{Todo, now};
+
+/// What the bottom line is currently collecting from the keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Adding(String),
+    Editing { id: u64, title: String },
+    Filtering(String),
+}
+
+/// Result of feeding one key to the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Nothing on disk needs to change.
+    Continue,
+    /// The store was modified and should be saved.
+    Changed,
+    Quit,
+}
+
+/// Triage view over one list of a [`Store`].
+pub struct App {
+    store: Store,
+    lists: Vec<String>,
+    list: usize,
+    filter: String,
+    mode: Mode,
+    state: ListState,
+    status: String,
+}
+
+impl App {
+    pub fn new(store: Store, list: String) -> Self {
+        let mut app = App {
+            store,
+            lists: Vec::new(),
+            list: 0,
+            filter: String::new(),
+            mode: Mode::Normal,
+            state: ListState::default(),
+            status: String::new(),
+        };
+        app.refresh_lists(&list);
+        app.state.select(Some(0));
+        app
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    fn list_name(&self) -> &str {
+        &self.lists[self.list]
+    }
+
+    fn refresh_lists(&mut self, current: &str) {
+        self.lists = self.store.lists().map(|(name, _)| name.to_string()).collect();
+        if !self.lists.iter().any(|name| name == current) {
+            self.lists.push(current.to_string());
+            self.lists.sort();
+        }
+        self.list = self.lists.iter().position(|name| name == current).unwrap_or(0);
+    }
+
+    /// Tasks in the current list that match the filter, in queue order.
+    fn visible(&self) -> Vec<&Todo> {
+        let filter = self.filter.to_lowercase();
+        self.store
+            .list(self.list_name())
+            .map(|queue| {
+                queue
+                    .iter()
+                    .filter(|t| t.title.to_lowercase().contains(&filter))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn selected(&self) -> Option<&Todo> {
+        self.visible().get(self.state.selected()?).copied()
+    }
+
+    /// Keeps the cursor on a real row after the visible set changes.
+    fn clamp_selection(&mut self) {
+        let len = self.visible().len();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.min(len.saturating_sub(1))));
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Outcome::Quit;
+        }
+
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal(key),
+            Mode::Adding(title) => self.handle_input(key, title, Mode::Adding, |app, title| {
+                if title.is_empty() {
+                    return Outcome::Continue;
+                }
+                let todo = Todo {
+                    id: app.store.next_id(),
+                    title,
+                    created_at: now(),
+                    ..Default::default()
+                };
+                app.status = format!("Added task {}", todo.id);
+                let list = app.list_name().to_string();
+                app.store.list_mut(&list).enqueue(todo);
+                Outcome::Changed
+            }),
+            Mode::Editing { id, title } => {
+                let editing = move |title| Mode::Editing { id, title };
+                self.handle_input(key, title, editing, move |app, title| {
+                    if title.is_empty() {
+                        return Outcome::Continue;
+                    }
+                    let list = app.list_name().to_string();
+                    match app.store.list_mut(&list).iter_mut().find(|t| t.id == id) {
+                        Some(todo) => {
+                            todo.title = title;
+                            app.status = format!("Renamed task {}", id);
+                            Outcome::Changed
+                        }
+                        None => Outcome::Continue,
+                    }
+                })
+            }
+            Mode::Filtering(filter) => {
+                let outcome = self.handle_input(key, filter, Mode::Filtering, |app, filter| {
+                    app.filter = filter;
+                    Outcome::Continue
+                });
+                // Filter as you type; Esc drops the filter altogether.
+                match &self.mode {
+                    Mode::Filtering(filter) => self.filter = filter.clone(),
+                    _ if key.code == KeyCode::Esc => self.filter.clear(),
+                    _ => {}
+                }
+                self.clamp_selection();
+                outcome
+            }
+        }
+    }
+
+    fn handle_normal(&mut self, key: KeyEvent) -> Outcome {
+        let len = self.visible().len();
+        let selected = self.state.selected().unwrap_or(0);
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Outcome::Quit,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.state.select(Some((selected + 1).min(len.saturating_sub(1))));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.state.select(Some(selected.saturating_sub(1))),
+            KeyCode::Tab => {
+                self.list = (self.list + 1) % self.lists.len();
+                self.state.select(Some(0));
+            }
+            KeyCode::Char('a') => self.mode = Mode::Adding(String::new()),
+            KeyCode::Char('e') => {
+                if let Some(todo) = self.selected() {
+                    self.mode = Mode::Editing { id: todo.id, title: todo.title.clone() };
+                }
+            }
+            KeyCode::Char('/') => self.mode = Mode::Filtering(self.filter.clone()),
+            KeyCode::Char('c') | KeyCode::Enter => return self.complete_selected(),
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Shared line editing for the add, edit and filter prompts. `resume`
+    /// rebuilds the mode while typing; `submit` runs on Enter.
+    fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        mut buffer: String,
+        resume: impl FnOnce(String) -> Mode,
+        submit: impl FnOnce(&mut Self, String) -> Outcome,
+    ) -> Outcome {
+        match key.code {
+            KeyCode::Esc => return Outcome::Continue,
+            KeyCode::Enter => return submit(self, buffer.trim().to_string()),
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) => buffer.push(c),
+            _ => {}
+        }
+        self.mode = resume(buffer);
+        Outcome::Continue
+    }
+
+    fn complete_selected(&mut self) -> Outcome {
+        let Some(id) = self.selected().map(|t| t.id) else {
+            return Outcome::Continue;
+        };
+
+        let list = self.list_name().to_string();
+        let Some(todo) = self.store.list_mut(&list).remove_where(|t| t.id == id) else {
+            return Outcome::Continue;
+        };
+        let (done, next) = self.store.finish(&list, todo, now());
+        self.status = match next {
+            Some(next) => format!("Completed '{}', next is task {}", done.todo.title, next),
+            None => format!("Completed '{}'", done.todo.title),
+        };
+        self.clamp_selection();
+        Outcome::Changed
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let now = now();
+        let visible = self.visible();
+        let mut title = format!(
+            " {} ({}/{}) ",
+            self.list_name(),
+            self.list + 1,
+            self.lists.len()
+        );
+        if !self.filter.is_empty() {
+            title.push_str(&format!("filter: {} ", self.filter));
+        }
+
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|t| {
+                let mut line = format!("{:>4}  {}  ({})", t.id, t.title, relative_age(now, t.created_at));
+                if let Some(due) = t.due_at {
+                    line.push_str(&format!(" due {}", relative_due(now, due)));
+                }
+                ListItem::new(line)
+            })
+            .collect();
+        let empty = items.is_empty();
+
+        frame.render_widget(Paragraph::new(Line::from(self.status.as_str())), header);
+
+        let block = Block::default().borders(Borders::ALL).title(title);
+        if empty {
+            frame.render_widget(Paragraph::new("No tasks").block(block), body);
+        } else {
+            let list = List::new(items)
+                .block(block)
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, body, &mut self.state);
+        }
+
+        let prompt = match &self.mode {
+            Mode::Normal => {
+                "j/k move  a add  e edit  c complete  / filter  tab next list  q quit".to_string()
+            }
+            Mode::Adding(text) => format!("New task: {}_", text),
+            Mode::Editing { title, .. } => format!("Rename: {}_", title),
+            Mode::Filtering(text) => format!("Filter: {}_", text),
+        };
+        frame.render_widget(Paragraph::new(prompt), footer);
+    }
+}
+
+/// Draws and feeds keys until the user quits or `keys` runs out, calling
+/// `save` after every change. The real terminal and the tests both go
+/// through here; only the backend and the key source differ.
+pub fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    keys: impl IntoIterator<Item = KeyEvent>,
+    mut save: impl FnMut(&Store) -> Result<(), TodoError>,
+) -> Result<(), TodoError> {
+    let io = |e| TodoError::Io { path: "terminal".to_string(), source: e };
+
+    terminal.draw(|frame| app.draw(frame)).map_err(io)?;
+    for key in keys {
+        match app.handle_key(key) {
+            Outcome::Quit => break,
+            Outcome::Changed => save(app.store())?,
+            Outcome::Continue => {}
+        }
+        terminal.draw(|frame| app.draw(frame)).map_err(io)?;
+    }
+    Ok(())
+}
+
+/// Key presses from the real terminal, ending if reading fails.
+pub fn terminal_keys() -> impl Iterator<Item = KeyEvent> {
+    std::iter::from_fn(|| loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => return Some(key),
+            Ok(_) => continue,
+            Err(_) => return None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn keys(script: &str) -> Vec<KeyEvent> {
+        script
+            .chars()
+            .map(|c| match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            })
+            .map(KeyEvent::from)
+            .collect()
+    }
+
+    fn titles(app: &App, list: &str) -> Vec<String> {
+        app.store().list(list).map(|q| q.iter().map(|t| t.title.clone()).collect()).unwrap_or_default()
+    }
+
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn scripted_session_adds_edits_and_completes() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        let mut app = App::new(Store::new(), "home".to_string());
+        let mut saves = 0;
+
+        let script = "abuy milk\nacall plumber\nawater plants\n\
+                      jc\
+                      e\x08\x08\x08\x08\x08\x08flowers\n";
+        run(&mut terminal, &mut app, keys(script), |_| {
+            saves += 1;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(titles(&app, "home"), ["buy milk", "water flowers"]);
+        assert_eq!(app.store().archive()[0].todo.title, "call plumber");
+        assert_eq!(saves, 5);
+        assert!(screen(&terminal).contains("water flowers"));
+    }
+
+    #[test]
+    fn filtering_narrows_the_view_and_escape_clears_it() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        let mut app = App::new(Store::new(), "home".to_string());
+
+        run(&mut terminal, &mut app, keys("aapples\nabananas\n/ban\n"), |_| Ok(())).unwrap();
+        let text = screen(&terminal);
+        assert!(text.contains("bananas") && !text.contains("apples"));
+
+        // Completing while filtered takes the selected match, not the queue head.
+        run(&mut terminal, &mut app, keys("c/\x1b"), |_| Ok(())).unwrap();
+        assert_eq!(titles(&app, "home"), ["apples"]);
+        assert!(screen(&terminal).contains("apples"));
+    }
+
+    #[test]
+    fn quit_stops_before_the_rest_of_the_script() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        let mut app = App::new(Store::new(), "home".to_string());
+
+        run(&mut terminal, &mut app, keys("qanever\n"), |_| Ok(())).unwrap();
+        assert!(titles(&app, "home").is_empty());
+    }
+}
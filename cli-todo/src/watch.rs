@@ -0,0 +1,148 @@
+use cli_todo::{Storage, TodoError};
+use std::collections::HashSet;
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::output::relative_due;
+use crate::store::Store;
+use crate::{Todo, now};
+
+/// A task that has come due, with the list it sits in.
+pub struct Reminder<'a> {
+    pub list: &'a str,
+    pub todo: &'a Todo,
+}
+
+/// Open tasks due at or before `now` and, when `since` is given, after it.
+/// Tasks in `seen` have already been reported and are skipped.
+pub fn due<'a>(store: &'a Store, since: Option<u64>, now: u64, seen: &HashSet<u64>) -> Vec<Reminder<'a>> {
+    let mut due: Vec<Reminder> = store
+        .lists()
+        .flat_map(|(list, queue)| queue.iter().map(move |todo| Reminder { list, todo }))
+        .filter(|r| match r.todo.due_at {
+            Some(due) => due <= now && since.is_none_or(|since| due > since) && !seen.contains(&r.todo.id),
+            None => false,
+        })
+        .collect();
+    due.sort_by_key(|r| (r.todo.due_at, r.todo.id));
+    due
+}
+
+/// Prints the reminder, or runs `exec` through `sh -c` with the task title as
+/// `$1` and `TODO_ID`, `TODO_TITLE`, `TODO_LIST` and `TODO_DUE` in the
+/// environment, e.g. `notify-send "Todo due" "$1"`.
+fn notify(reminder: &Reminder, exec: Option<&str>, now: u64) {
+    let todo = reminder.todo;
+    let due = todo.due_at.unwrap_or(now);
+    let Some(exec) = exec else {
+        println!("due: [{}] {} {} ({})", reminder.list, todo.id, todo.title, relative_due(now, due));
+        return;
+    };
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(exec)
+        .arg("cli-todo")
+        .arg(&todo.title)
+        .env("TODO_ID", todo.id.to_string())
+        .env("TODO_TITLE", &todo.title)
+        .env("TODO_LIST", reminder.list)
+        .env("TODO_DUE", due.to_string())
+        .status();
+
+    // A broken hook should not stop the watcher; report it and carry on.
+    match status {
+        Ok(status) if !status.success() => eprintln!("warning: reminder command exited with {}", status),
+        Err(e) => eprintln!("warning: could not run reminder command: {}", e),
+        Ok(_) => {}
+    }
+}
+
+fn modified(storage: &dyn Storage<Store>) -> Option<SystemTime> {
+    fs::metadata(storage.path()?).and_then(|meta| meta.modified()).ok()
+}
+
+/// With `once`, reports the tasks that came due during the last `interval`
+/// seconds and returns, so a cron job running every `interval` seconds sees
+/// each task exactly once. Otherwise reports everything already overdue, then
+/// keeps polling, re-reading the data file whenever it changes.
+pub fn run(
+    storage: &mut dyn Storage<Store>,
+    exec: Option<&str>,
+    interval: u64,
+    once: bool,
+) -> Result<(), TodoError> {
+    let mut store = Store::load(storage)?;
+
+    if once {
+        let now = now();
+        for reminder in due(&store, Some(now.saturating_sub(interval)), now, &HashSet::new()) {
+            notify(&reminder, exec, now);
+        }
+        return Ok(());
+    }
+
+    let mut seen = HashSet::new();
+    let mut last_modified = modified(storage);
+    loop {
+        let now = now();
+        for reminder in due(&store, None, now, &seen) {
+            notify(&reminder, exec, now);
+            seen.insert(reminder.todo.id);
+        }
+
+        thread::sleep(Duration::from_secs(interval.max(1)));
+
+        let current = modified(storage);
+        if current != last_modified || current.is_none() {
+            last_modified = current;
+            // Keep the previous state if the file is mid-write or broken.
+            match Store::load(storage) {
+                Ok(fresh) => store = fresh,
+                Err(e) => eprintln!("warning: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(store: &mut Store, list: &str, title: &str, due_at: Option<u64>) -> u64 {
+        let id = store.next_id();
+        let todo = Todo { id, title: title.to_string(), due_at, ..Default::default() };
+        store.list_mut(list).enqueue(todo);
+        id
+    }
+
+    fn titles(reminders: &[Reminder]) -> Vec<String> {
+        reminders.iter().map(|r| format!("{}/{}", r.list, r.todo.title)).collect()
+    }
+
+    #[test]
+    fn due_finds_overdue_tasks_across_lists() {
+        let mut store = Store::new();
+        add(&mut store, "work", "report", Some(50));
+        add(&mut store, "home", "bins", Some(10));
+        add(&mut store, "home", "later", Some(500));
+        add(&mut store, "home", "undated", None);
+
+        let reminders = due(&store, None, 100, &HashSet::new());
+        assert_eq!(titles(&reminders), ["home/bins", "work/report"]);
+    }
+
+    #[test]
+    fn due_respects_window_and_seen() {
+        let mut store = Store::new();
+        let old = add(&mut store, "home", "old", Some(10));
+        add(&mut store, "home", "new", Some(90));
+
+        assert_eq!(titles(&due(&store, Some(60), 100, &HashSet::new())), ["home/new"]);
+
+        let seen = HashSet::from([old]);
+        assert_eq!(titles(&due(&store, None, 100, &seen)), ["home/new"]);
+    }
+}